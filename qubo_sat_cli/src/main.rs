@@ -3,21 +3,35 @@ use std::{
     io::{self},
     path::PathBuf,
 };
+use std::collections::hash_map::RandomState;
 use std::error::Error;
-use std::io::Read;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufWriter, Read};
 use std::num::{NonZero, NonZeroUsize};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
 
 use clap::{self, Parser, ValueEnum};
-use log::{debug, error, info, LevelFilter, set_max_level, trace};
+use log::{debug, error, info, Level, LevelFilter, log_enabled, set_logger, set_max_level, trace, warn};
 
-use optimizers::core::{Reduction, Solver};
-use optimizers::qubo::{QuboProblem, QuboSolution};
-use optimizers::qubo::solvers::{ExhaustiveSearch, MomentumAnnealer, Mopso, ParallelExhaustiveSearch, SimulatedAnnealer};
+use optimizers::core::{BoxedReduction, Chain, Reducer, Reduction, SampleSet, Solver, StoppingCriteria};
+use optimizers::logging::{CsvObserver, JsonLinesObserver, LogObserver, SolverObserver};
+use optimizers::qubo::{QuboProblem, QuboSolution, QuboWeight};
+use optimizers::qubo::reductions::roof_duality::RoofDuality;
+use optimizers::qubo::solvers::{BifurcationVariant, BranchAndBound, Exponential, ExhaustiveSearch, Geometric, GrayCodeSearch, Linear, Logarithmic, MomentumAnnealer, Mopso, ParallelExhaustiveSearch, ParallelTempering, PopulationAnnealer, Schedule, SimulatedAnnealer, SimulatedBifurcation, SimulatedQuantumAnnealer, TabuSearch};
 use optimizers::sat::{KSatProblem, SatSolution};
 use optimizers::sat::reductions::qubo::chancellor::Chancellor;
 use optimizers::sat::reductions::qubo::choi::Choi;
 use optimizers::sat::reductions::qubo::nusslein23::Nusslein23;
 use optimizers::sat::reductions::qubo::nusslein::Nusslein;
+use optimizers::sat::reductions::three_sat::ThreeSat;
+
+use crate::output::{CommentLogger, write_qubo_solution, write_solution};
+
+mod output;
+
+static LOGGER: CommentLogger = CommentLogger;
 
 #[derive(ValueEnum, Debug, Clone)]
 #[clap(rename_all = "kebab_case")]
@@ -25,70 +39,132 @@ enum SolverOptions {
     SimulatedAnnealing,
     ExhaustiveSearch,
     ParallelExhaustiveSearch,
+    BranchAndBound,
+    GrayCodeSearch,
     MomentumAnnealing,
-    Mopso
+    Mopso,
+    TabuSearch,
+    ParallelTempering,
+    PopulationAnnealing,
+    SimulatedQuantumAnnealing,
+    BallisticBifurcation,
+    DiscreteBifurcation,
 }
 
-impl Solver<QuboProblem> for SolverOptions {
-    fn solve(&mut self, qubo_problem: &QuboProblem) -> QuboSolution {
+type Observer<T> = Arc<dyn SolverObserver<T>>;
+
+impl SolverOptions {
+    /// Builds the solver, seeding it if it is stochastic, and replacing the default temperature
+    /// schedule of the annealers if one is given
+    fn build<T>(&self, observers: Vec<Observer<T>>, seed: u64, temperature: Option<TemperatureSchedule>) -> Box<dyn Solver<QuboProblem<T>>>
+        where
+            T: QuboWeight + 'static,
+    {
         match self {
-            Self::SimulatedAnnealing => SimulatedAnnealer::new(NonZero::new(1_000).unwrap(), std::thread::available_parallelism().unwrap()).solve(qubo_problem),
-            Self::ExhaustiveSearch => ExhaustiveSearch::new().solve(qubo_problem),
-            Self::ParallelExhaustiveSearch => ParallelExhaustiveSearch::new(NonZeroUsize::new((usize::BITS as usize) - std::thread::available_parallelism().unwrap().get()).unwrap()).solve(qubo_problem),
-            Self::MomentumAnnealing => MomentumAnnealer::new(1_000).solve(qubo_problem),
-            Self::Mopso => Mopso::new().solve(qubo_problem)
+            Self::SimulatedAnnealing => {
+                let max_iterations = NonZero::new(1_000).unwrap();
+                let solver = match temperature {
+                    Some(temperature) => SimulatedAnnealer::new(max_iterations, std::thread::available_parallelism().unwrap(), temperature.build(max_iterations.get())),
+                    None => SimulatedAnnealer::new(max_iterations, std::thread::available_parallelism().unwrap(), SimulatedAnnealer::default_temperature(max_iterations)),
+                };
+
+                Box::new(observers.into_iter().fold(solver.with_seed(seed), SimulatedAnnealer::with_observer))
+            }
+            Self::ExhaustiveSearch => Box::new(ExhaustiveSearch::new()),
+            // Enough prefixes to give every thread at least one sub tree
+            Self::ParallelExhaustiveSearch => Box::new(ParallelExhaustiveSearch::new(NonZeroUsize::new(std::thread::available_parallelism().unwrap().get().next_power_of_two().ilog2() as usize + 1).unwrap())),
+            Self::BranchAndBound => Box::new(observers.into_iter().fold(BranchAndBound::new().with_seed(seed), BranchAndBound::with_observer)),
+            Self::GrayCodeSearch => Box::new(GrayCodeSearch::new()),
+            Self::MomentumAnnealing => {
+                let max_iterations = 1_000;
+                let solver = match temperature {
+                    Some(temperature) => MomentumAnnealer::new(max_iterations, temperature.build(max_iterations), MomentumAnnealer::default_dropout(), MomentumAnnealer::default_momentum_scaling()),
+                    None => MomentumAnnealer::new(max_iterations, MomentumAnnealer::default_temperature(), MomentumAnnealer::default_dropout(), MomentumAnnealer::default_momentum_scaling()),
+                };
+
+                Box::new(observers.into_iter().fold(solver.with_seed(seed), MomentumAnnealer::with_observer))
+            }
+            Self::Mopso => Box::new(observers.into_iter().fold(Mopso::new(NonZero::new(64).unwrap(), 0.7, NonZero::new(1_000).unwrap()).with_seed(seed), Mopso::with_observer)),
+            Self::ParallelTempering => Box::new(observers.into_iter().fold(ParallelTempering::new(NonZero::new(1_000).unwrap(), NonZero::new(16).unwrap()).with_seed(seed), ParallelTempering::with_observer)),
+            Self::PopulationAnnealing => Box::new(observers.into_iter().fold(PopulationAnnealer::new(NonZero::new(100).unwrap(), NonZero::new(256).unwrap()).with_seed(seed), PopulationAnnealer::with_observer)),
+            Self::SimulatedQuantumAnnealing => Box::new(observers.into_iter().fold(SimulatedQuantumAnnealer::new(NonZero::new(1_000).unwrap(), NonZero::new(16).unwrap()).with_seed(seed), SimulatedQuantumAnnealer::with_observer)),
+            Self::BallisticBifurcation => Box::new(observers.into_iter().fold(SimulatedBifurcation::new(NonZero::new(1_000).unwrap(), std::thread::available_parallelism().unwrap()).with_seed(seed), SimulatedBifurcation::with_observer)),
+            Self::DiscreteBifurcation => Box::new(observers.into_iter().fold(SimulatedBifurcation::new(NonZero::new(1_000).unwrap(), std::thread::available_parallelism().unwrap()).with_variant(BifurcationVariant::Discrete).with_seed(seed), SimulatedBifurcation::with_observer)),
+            Self::TabuSearch => Box::new(observers.into_iter().fold(TabuSearch::new(NonZero::new(10_000).unwrap(), std::thread::available_parallelism().unwrap()).with_restarts(NonZero::new(1_000).unwrap()).with_seed(seed), TabuSearch::with_observer)),
         }
     }
 }
 
 #[derive(ValueEnum, Debug, Clone)]
 #[clap(rename_all = "kebab_case")]
-enum ReducerOptions {
-    Chancellor,
-    Choi,
-    Nusslein,
-    Nusslein23,
+enum ScheduleOptions {
+    Linear,
+    Geometric,
+    Exponential,
+    /// Only reaches the end temperature, as it starts from an infinite one
+    Logarithmic,
 }
 
-enum ReducerWrapper {
-    Chancellor(Chancellor),
-    Choi(Choi),
-    Nusslein(Nusslein),
-    Nusslein23(Nusslein23),
+/// A temperature schedule for the annealers given on the command line
+struct TemperatureSchedule {
+    kind: ScheduleOptions,
+    start: f64,
+    end: f64,
 }
 
-impl ReducerWrapper {
-    fn new(option: ReducerOptions, ksat_problem: &KSatProblem) -> (QuboProblem, Self) {
-        match option {
-            ReducerOptions::Chancellor => {
-                let (q, r) = Chancellor::reduce(ksat_problem);
+impl TemperatureSchedule {
+    /// Goes from the start to the end temperature over `nb_steps` steps
+    fn build(&self, nb_steps: usize) -> impl Schedule + 'static {
+        let (start, end) = (self.start, self.end);
 
-                (q, Self::Chancellor(r))
-            }
-            ReducerOptions::Choi => {
-                let (q, r) = Choi::reduce(ksat_problem);
+        let schedule: Box<dyn Schedule> = match self.kind {
+            ScheduleOptions::Linear => Box::new(Linear::new(start, end, nb_steps)),
+            ScheduleOptions::Geometric => Box::new(Geometric::new(start, end, nb_steps)),
+            ScheduleOptions::Exponential => Box::new(Exponential::new(start, f64::ln(start / end) / nb_steps as f64)),
+            ScheduleOptions::Logarithmic => Box::new(Logarithmic::new(end * f64::ln_1p(nb_steps as f64))),
+        };
 
-                (q, Self::Choi(r))
-            }
-            ReducerOptions::Nusslein => {
-                let (q, r) = Nusslein::reduce(ksat_problem);
+        move |k| schedule.value(k)
+    }
+}
 
-                (q, Self::Nusslein(r))
-            }
-            ReducerOptions::Nusslein23 => {
-                let (q, r) = Nusslein23::reduce(ksat_problem);
+#[derive(ValueEnum, Debug, Clone)]
+#[clap(rename_all = "kebab_case")]
+enum FormatOptions {
+    /// A DIMACS CNF file, which is reduced to a QUBO before it is solved
+    Cnf,
+    /// A qbsolv `.qubo` file
+    Qbsolv,
+    /// A Matrix Market coordinate file
+    MatrixMarket,
+    /// An ORLib `bqp` file, which may hold several problems
+    Bqp,
+}
 
-                (q, Self::Nusslein23(r))
-            }
-        }
-    }
+#[derive(ValueEnum, Debug, Clone)]
+#[clap(rename_all = "kebab_case")]
+enum LogFormatOptions {
+    Csv,
+    JsonLines,
+}
 
-    fn up_model(self, solution: QuboSolution) -> SatSolution {
+#[derive(ValueEnum, Debug, Clone)]
+#[clap(rename_all = "kebab_case")]
+enum ReducerOptions {
+    Chancellor,
+    Choi,
+    Nusslein,
+    Nusslein23,
+}
+
+impl ReducerOptions {
+    fn reducer(&self) -> Reducer<KSatProblem, QuboProblem> {
         match self {
-            ReducerWrapper::Chancellor(r) => r.up_model(solution),
-            ReducerWrapper::Choi(r) => r.up_model(solution),
-            ReducerWrapper::Nusslein(r) => r.up_model(solution),
-            ReducerWrapper::Nusslein23(r) => r.up_model(solution),
+            // Chancellor's gadget only implements clauses of exactly three literals
+            Self::Chancellor => BoxedReduction::reduce::<Chain<ThreeSat, Chancellor, KSatProblem>>,
+            Self::Choi => BoxedReduction::reduce::<Choi>,
+            Self::Nusslein => BoxedReduction::reduce::<Nusslein>,
+            Self::Nusslein23 => BoxedReduction::reduce::<Nusslein23>,
         }
     }
 }
@@ -98,12 +174,18 @@ struct SolverCli {
     /// Do not log anything; Overrides verbose
     #[arg(short = 'q', long = "quiet")]
     quiet: bool,
-    // The file to log solver steps to in csv format
+    /// The file to log solver steps to
     #[arg(short = 'l', long = "log")]
     log_file: Option<PathBuf>,
-    /// A standard DIMACS CNF file to read. If not provided it will attempt to read a CNF file from the STDIN
+    /// The format of the solver step log
+    #[arg(value_enum, long = "log-format", default_value_t = LogFormatOptions::Csv)]
+    log_format: LogFormatOptions,
+    /// The problem file to read. If not provided it will attempt to read the problem from the STDIN
     #[arg()]
     file: Option<PathBuf>,
+    /// The format of the problem file
+    #[arg(value_enum, long = "format", default_value_t = FormatOptions::Cnf)]
+    format: FormatOptions,
     /// Logs more information about the program. Repeat to increase verbosity
     #[arg(short = 'v', long = "verbose", action = clap::ArgAction::Count)]
     verbose: u8,
@@ -113,9 +195,105 @@ struct SolverCli {
     // The solver to use
     #[arg(value_enum, long = "solver", default_value_t = SolverOptions::ParallelExhaustiveSearch)]
     solver: SolverOptions,
+    /// Stop the solver after this many seconds and use the best solution found so far
+    #[arg(long = "time-limit")]
+    time_limit: Option<f64>,
+    /// Stop the solver as soon as it finds a solution with at most this energy
+    #[arg(long = "target-energy", allow_negative_numbers = true)]
+    target_energy: Option<f64>,
+    /// Stop the solver after it has evaluated this many solutions
+    #[arg(long = "max-evaluations")]
+    max_evaluations: Option<usize>,
+    /// Fix the variables whose optimal values are known from roof duality before solving
+    #[arg(long = "roof-duality")]
+    roof_duality: bool,
+    /// The seed of the stochastic solvers. A random one is used and logged if it is not given
+    #[arg(long = "seed")]
+    seed: Option<u64>,
+    /// The temperature schedule of the simulated and momentum annealers, instead of their defaults
+    #[arg(value_enum, long = "schedule", requires_all = ["schedule_start", "schedule_end"])]
+    schedule: Option<ScheduleOptions>,
+    /// The temperature at the start of the schedule
+    #[arg(long = "schedule-start", requires = "schedule")]
+    schedule_start: Option<f64>,
+    /// The temperature at the end of the schedule
+    #[arg(long = "schedule-end", requires = "schedule")]
+    schedule_end: Option<f64>,
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
+impl SolverCli {
+    fn open_input(&self) -> io::Result<Box<dyn Read>> {
+        match &self.file {
+            None => {
+                debug!("Reading problem from STDIN");
+
+                Ok(Box::new(io::stdin()))
+            }
+            Some(path) => {
+                debug!("Reading problem from file \"{}\"", path.to_string_lossy());
+
+                Ok(Box::new(File::open(path)?))
+            }
+        }
+    }
+
+    fn observers<T>(&self) -> Result<Vec<Observer<T>>, Box<dyn Error>>
+        where
+            T: QuboWeight + 'static,
+    {
+        let mut observers: Vec<Observer<T>> = Vec::new();
+
+        if log_enabled!(target: "solver_performance", Level::Info) {
+            observers.push(Arc::new(LogObserver));
+        }
+
+        if let Some(path) = &self.log_file {
+            debug!("Logging solver steps to \"{}\"", path.to_string_lossy());
+
+            let file = BufWriter::new(File::create(path)?);
+            observers.push(match self.log_format {
+                LogFormatOptions::Csv => Arc::new(CsvObserver::new(file)?),
+                LogFormatOptions::JsonLines => Arc::new(JsonLinesObserver::new(file)),
+            });
+        }
+
+        Ok(observers)
+    }
+
+    fn temperature(&self) -> Option<TemperatureSchedule> {
+        Some(TemperatureSchedule {
+            kind: self.schedule.clone()?,
+            start: self.schedule_start?,
+            end: self.schedule_end?,
+        })
+    }
+
+    /// Builds the stopping criteria, scaling the target energy by `energy_scale` and removing
+    /// `energy_offset` to match the energy of the problem given to the solver
+    fn criteria<T>(&self, energy_scale: f64, energy_offset: T) -> Result<StoppingCriteria<T>, Box<dyn Error>>
+        where
+            T: QuboWeight,
+    {
+        let mut criteria = StoppingCriteria::new();
+
+        if let Some(time_limit) = self.time_limit {
+            criteria = criteria.with_time_limit(Duration::from_secs_f64(time_limit));
+        }
+        if let Some(target_energy) = self.target_energy {
+            let target_energy = T::from(target_energy * energy_scale)
+                .ok_or("Target energy cannot be represented by the problem weights")?;
+
+            criteria = criteria.with_target_energy(target_energy - energy_offset);
+        }
+        if let Some(max_evaluations) = self.max_evaluations {
+            criteria = criteria.with_max_evaluations(max_evaluations);
+        }
+
+        Ok(criteria)
+    }
+}
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
     let args = SolverCli::parse();
 
     let verbosity = if args.quiet {
@@ -128,55 +306,122 @@ fn main() -> Result<(), Box<dyn Error>> {
             _ => LevelFilter::Trace,
         }
     };
-    
-    
-    
+
+    set_logger(&LOGGER)?;
     set_max_level(verbosity);
 
     info!("Current Verbosity is {}", verbosity);
 
-    let problem = {
-        let file: Box<dyn Read> = match args.file {
-            None => {
-                debug!("Reading problem from STDIN");
-
-                Box::new(io::stdin())
-            }
-            Some(path) => {
-                debug!("Reading problem from file \"{}\"", path.to_string_lossy());
-
-                Box::new(File::open(path)?)
-            }
-        };
+    match args.format {
+        FormatOptions::Cnf => solve_cnf(&args),
+        _ => solve_qubo(&args),
+    }
+}
 
-        KSatProblem::from_benchmark_file(file)?
-    };
+fn solve_cnf(args: &SolverCli) -> Result<ExitCode, Box<dyn Error>> {
+    let problem = KSatProblem::from_benchmark_file(args.open_input()?)?;
 
     trace!("Ingested problem {:?}", problem);
 
-    let (qubo_problem, up_modeller) = {
-        ReducerWrapper::new(args.reducer, &problem)
-    };
+    let (qubo_problem, up_modeller) = args.reducer.reducer()(&problem);
 
     debug!("Reduced problem size is {}", qubo_problem.get_size());
     trace!("Reduced problem produced {:?}", qubo_problem);
 
-    let mut solver = {
-        args.solver
-    };
-
-    let qubo_solution = solver.solve(&qubo_problem);
+    let samples = solve(args, &qubo_problem, 1.0, args.observers()?)?;
+    debug!("Solver returned {} distinct solutions", samples.len());
 
-    let mut solution = up_modeller.up_model(qubo_solution);
+    // A solution above the lowest energy can still satisfy the problem, so every one is tried in turn
+    let mut candidates = samples.into_iter().map(|sample| up_modeller.up_model(sample.solution));
+    let first = candidates.next().expect("Solvers return at least one sample");
+    let mut solution = if is_satisfying(&problem, &first) {
+        first
+    } else {
+        candidates.find(|candidate| is_satisfying(&problem, candidate)).unwrap_or(first)
+    };
 
     debug!("{:?}", solution);
 
+    if let SatSolution::Unsat = solution {
+        // The reductions are heuristic, so a conflicting model does not prove the problem is unsatisfiable
+        warn!("Up-modelling found a conflict, which does not prove the problem is unsatisfiable");
+        solution = SatSolution::Unknown
+    }
+
     if !problem.evaluate(&solution) {
         error!("Solution that was generated does not satisfy the problem!");
         solution = SatSolution::Unknown
     }
 
-    println!("{}", solution);
+    Ok(write_solution(&mut io::stdout().lock(), &solution)?)
+}
+
+fn is_satisfying(problem: &KSatProblem, solution: &SatSolution) -> bool {
+    matches!(solution, SatSolution::Sat(_)) && problem.evaluate(solution)
+}
+
+fn solve_qubo(args: &SolverCli) -> Result<ExitCode, Box<dyn Error>> {
+    let file = args.open_input()?;
+    let problems: Vec<QuboProblem<f64>> = match args.format {
+        FormatOptions::Cnf => unreachable!("CNF files are reduced before they are solved"),
+        FormatOptions::Qbsolv => vec![QuboProblem::from_qbsolv_file(file)?],
+        FormatOptions::MatrixMarket => vec![QuboProblem::from_matrix_market_file(file)?],
+        FormatOptions::Bqp => QuboProblem::from_bqp_file(file)?,
+    };
+
+    let observers = args.observers()?;
+
+    for (i, problem) in problems.iter().enumerate() {
+        debug!("Solving problem {} of size {}", i + 1, problem.get_size());
+        trace!("Ingested problem {:?}", problem);
+
+        // The problems store Q + Q^T, so the solvers see twice the energy defined by the file
+        let samples = solve(args, problem, 2.0, observers.clone())?;
+        debug!("Solver returned {} distinct solutions", samples.len());
+        let solution = &samples.best().expect("Solvers return at least one sample").solution;
+
+        // The output is not locked while solving, as the solvers may log from other threads
+        write_qubo_solution(&mut io::stdout().lock(), problem.evaluate(solution) / 2.0, solution)?;
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Samples a QUBO problem with the chosen solver, first fixing the variables given by roof duality if
+/// it was requested
+fn solve<T>(
+    args: &SolverCli,
+    problem: &QuboProblem<T>,
+    energy_scale: f64,
+    observers: Vec<Observer<T>>,
+) -> Result<SampleSet<QuboSolution, T>, Box<dyn Error>>
+    where
+        T: QuboWeight + 'static,
+{
+    let seed = args.seed.unwrap_or_else(|| RandomState::new().build_hasher().finish());
+    info!("Using seed {seed}");
+
+    let mut solver = args.solver.build(observers, seed, args.temperature());
+
+    if !args.roof_duality {
+        return Ok(solver.sample_until(problem, &args.criteria(energy_scale, T::zero())?));
+    }
+
+    let (reduced_problem, reducer) = RoofDuality::reduce(problem);
+    info!("Roof duality fixed {} of {} variables", reducer.nb_fixed(), problem.get_size());
+
+    let criteria = args.criteria(energy_scale, reducer.offset())?;
+    let samples = if reduced_problem.get_size() == 0 {
+        // Nothing is left to search, and not every solver handles empty problems
+        ExhaustiveSearch::new().sample(&reduced_problem)
+    } else {
+        solver.sample_until(&reduced_problem, &criteria)
+    };
+
+    Ok(samples.map(|solution, _| {
+        let solution = reducer.up_model(solution);
+        let evaluation = problem.evaluate(&solution);
 
-    Ok(())
+        (solution, evaluation)
+    }))
 }
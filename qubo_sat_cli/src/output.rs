@@ -0,0 +1,83 @@
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+use log::{Log, Metadata, Record};
+
+use optimizers::qubo::QuboSolution;
+use optimizers::sat::SatSolution;
+
+// Keeps `v` lines readable in terminals and within the limits of older benchmarking scripts
+const MAX_LINE_WIDTH: usize = 78;
+
+/// Writes log records to STDOUT as `c` comment lines, so that they can be interleaved with the
+/// solution lines without confusing benchmarking harnesses
+pub struct CommentLogger;
+
+impl Log for CommentLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let mut stdout = io::stdout().lock();
+        for line in record.args().to_string().lines() {
+            // There is nowhere left to report a failure to write a diagnostic
+            let _ = writeln!(stdout, "c [{}] {}", record.level(), line);
+        }
+    }
+
+    fn flush(&self) {
+        let _ = io::stdout().flush();
+    }
+}
+
+/// Writes the `s` status line and, for satisfiable problems, the `v` lines listing the model.
+/// Returns the exit code expected by the SAT competition: 10 for SAT, 20 for UNSAT and 0 otherwise.
+pub fn write_solution(writer: &mut impl Write, solution: &SatSolution) -> io::Result<ExitCode> {
+    match solution {
+        SatSolution::Sat(model) => {
+            writeln!(writer, "s SATISFIABLE")?;
+
+            let literals = model
+                .iter()
+                .enumerate()
+                .map(|(i, is_true)| if *is_true { format!("{}", i + 1) } else { format!("-{}", i + 1) })
+                .chain(["0".to_string()]);
+
+            let mut line = String::from("v");
+            for literal in literals {
+                if line.len() + 1 + literal.len() > MAX_LINE_WIDTH {
+                    writeln!(writer, "{}", line)?;
+                    line = String::from("v");
+                }
+
+                line.push(' ');
+                line.push_str(&literal);
+            }
+            writeln!(writer, "{}", line)?;
+
+            Ok(ExitCode::from(10))
+        }
+        SatSolution::Unsat => {
+            writeln!(writer, "s UNSATISFIABLE")?;
+
+            Ok(ExitCode::from(20))
+        }
+        SatSolution::Unknown => {
+            writeln!(writer, "s UNKNOWN")?;
+
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
+
+/// Writes the energy of a QUBO solution on an `o` line, followed by its assignment as a bit string
+/// on a `v` line
+pub fn write_qubo_solution(writer: &mut impl Write, energy: f64, solution: &QuboSolution) -> io::Result<()> {
+    writeln!(writer, "o {}", energy)?;
+    writeln!(writer, "v {}", solution)
+}
@@ -1,5 +1,13 @@
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
 pub trait Problem {
     type Solution;
+    type Energy;
 }
 
 pub trait Reduction<U, V>
@@ -12,9 +20,416 @@ pub trait Reduction<U, V>
     fn up_model(&self, solution: V::Solution) -> U::Solution;
 }
 
+/// A reduction that can also map a solution of the original problem onto the reduced problem, e.g. to
+/// warm start a solver from a candidate found by a previous run or by another solver. The reduced
+/// problem is passed along for the reductions that pick the values of their auxiliary variables.
+pub trait DownModel<U, V>: Reduction<U, V>
+    where
+        U: Problem,
+        V: Problem,
+{
+    fn down_model(&self, reduced_problem: &V, solution: U::Solution) -> V::Solution;
+}
+
+/// Chains a `Reduction<A, B>` and a `Reduction<B, C>` into a `Reduction<A, C>`. Solutions are
+/// up-modelled through the second reduction and then the first. `B` is the intermediate problem,
+/// which is kept to down-model solutions through both reductions.
+pub struct Chain<R1, R2, B> {
+    first: R1,
+    second: R2,
+    intermediate: B,
+}
+
+impl<R1, R2, B> Chain<R1, R2, B> {
+    pub fn first(&self) -> &R1 {
+        &self.first
+    }
+
+    pub fn second(&self) -> &R2 {
+        &self.second
+    }
+
+    pub fn intermediate(&self) -> &B {
+        &self.intermediate
+    }
+}
+
+impl<A, B, C, R1, R2> Reduction<A, C> for Chain<R1, R2, B>
+    where
+        A: Problem,
+        B: Problem,
+        C: Problem,
+        R1: Reduction<A, B>,
+        R2: Reduction<B, C>,
+{
+    fn reduce(problem: &A) -> (C, Self) {
+        let (intermediate_problem, first) = R1::reduce(problem);
+        let (reduced_problem, second) = R2::reduce(&intermediate_problem);
+
+        (
+            reduced_problem,
+            Self {
+                first,
+                second,
+                intermediate: intermediate_problem,
+            },
+        )
+    }
+
+    fn up_model(&self, solution: C::Solution) -> A::Solution {
+        self.first.up_model(self.second.up_model(solution))
+    }
+}
+
+impl<A, B, C, R1, R2> DownModel<A, C> for Chain<R1, R2, B>
+    where
+        A: Problem,
+        B: Problem,
+        C: Problem,
+        R1: DownModel<A, B>,
+        R2: DownModel<B, C>,
+{
+    fn down_model(&self, reduced_problem: &C, solution: A::Solution) -> C::Solution {
+        self.second.down_model(reduced_problem, self.first.down_model(&self.intermediate, solution))
+    }
+}
+
+/// A reduction whose type is only known at runtime. It can only up-model solutions, as the
+/// reduction itself has already been applied when it is created.
+pub struct BoxedReduction<U, V>(Box<dyn Fn(V::Solution) -> U::Solution>)
+    where
+        U: Problem,
+        V: Problem;
+
+/// Reduces a problem with a reduction picked at runtime, e.g. `BoxedReduction::reduce::<R>`
+pub type Reducer<U, V> = fn(&U) -> (V, BoxedReduction<U, V>);
+
+impl<U, V> BoxedReduction<U, V>
+    where
+        U: Problem + 'static,
+        V: Problem + 'static,
+{
+    pub fn reduce<R>(problem: &U) -> (V, Self)
+        where
+            R: Reduction<U, V> + 'static,
+    {
+        let (reduced_problem, reduction) = R::reduce(problem);
+
+        (reduced_problem, Self(Box::new(move |solution| reduction.up_model(solution))))
+    }
+
+    pub fn up_model(&self, solution: V::Solution) -> U::Solution {
+        (self.0)(solution)
+    }
+
+    /// Up-models solutions of `W` through `next` and then through this reduction
+    pub fn then<W>(self, next: BoxedReduction<V, W>) -> BoxedReduction<U, W>
+        where
+            W: Problem + 'static,
+    {
+        BoxedReduction(Box::new(move |solution| self.up_model(next.up_model(solution))))
+    }
+}
+
 pub trait Solver<T>
     where
         T: Problem,
 {
-    fn solve(&mut self, problem: &T) -> T::Solution;
+    fn solve(&mut self, problem: &T) -> T::Solution {
+        self.solve_until(problem, &StoppingCriteria::default())
+    }
+
+    /// Solves the problem until the solver finishes or any of the stopping criteria fire, in which
+    /// case the best solution found so far is returned.
+    fn solve_until(&mut self, problem: &T, criteria: &StoppingCriteria<T::Energy>) -> T::Solution {
+        self.sample_until(problem, criteria)
+            .into_iter()
+            .next()
+            .expect("Solvers return at least one sample")
+            .solution
+    }
+
+    fn sample(&mut self, problem: &T) -> SampleSet<T::Solution, T::Energy> {
+        self.sample_until(problem, &StoppingCriteria::default())
+    }
+
+    /// Solves the problem like [Solver::solve_until], but returns every solution that the solver
+    /// ended with, e.g. the best solution of each of its replicas, rather than only the best one.
+    fn sample_until(&mut self, problem: &T, criteria: &StoppingCriteria<T::Energy>) -> SampleSet<T::Solution, T::Energy>;
+}
+
+/// A distinct solution returned by a solver, along with its energy
+#[derive(Clone, Debug)]
+pub struct Sample<S, E> {
+    pub solution: S,
+    pub energy: E,
+    /// The number of times that the solver returned the solution
+    pub occurrences: usize,
+    /// The replicas that returned the solution, in increasing order. Solvers without replicas use `0`.
+    pub replicas: Vec<usize>,
+}
+
+/// The distinct solutions returned by a solver, sorted by energy. Solutions with equal energies stay
+/// in the order in which the solver returned them, so the first one is what [Solver::solve_until]
+/// returns.
+#[derive(Clone, Debug)]
+pub struct SampleSet<S, E> {
+    samples: Vec<Sample<S, E>>,
+}
+
+impl<S, E> SampleSet<S, E>
+    where
+        S: Eq + Hash,
+        E: PartialOrd,
+{
+    /// Merges the samples of equal solutions, keeping the energy of the first one, and sorts them
+    pub fn from_samples(samples: impl IntoIterator<Item=Sample<S, E>>) -> Self {
+        let samples: Vec<_> = samples.into_iter().collect();
+
+        // The position of the first occurrence of every solution
+        let mut first_positions = HashMap::with_capacity(samples.len());
+        let targets: Vec<usize> = samples
+            .iter()
+            .enumerate()
+            .map(|(i, sample)| *first_positions.entry(&sample.solution).or_insert(i))
+            .collect();
+        drop(first_positions);
+
+        let mut merged: Vec<Sample<S, E>> = Vec::with_capacity(samples.len());
+        let mut merged_positions = vec![0; samples.len()];
+        for (i, (sample, target)) in samples.into_iter().zip(targets).enumerate() {
+            if target == i {
+                merged_positions[i] = merged.len();
+                merged.push(sample);
+            } else {
+                let first = &mut merged[merged_positions[target]];
+                first.occurrences += sample.occurrences;
+                first.replicas.extend(sample.replicas);
+            }
+        }
+
+        for sample in &mut merged {
+            sample.replicas.sort_unstable();
+        }
+        merged.sort_by(|a, b| a.energy.partial_cmp(&b.energy).unwrap_or(CmpOrdering::Equal));
+
+        Self { samples: merged }
+    }
+
+    /// Transforms every solution and its energy, e.g. to up-model them through a reduction, merging
+    /// the solutions that become equal
+    pub fn map<S2, E2>(self, f: impl Fn(S, E) -> (S2, E2)) -> SampleSet<S2, E2>
+        where
+            S2: Eq + Hash,
+            E2: PartialOrd,
+    {
+        SampleSet::from_samples(self.samples.into_iter().map(|sample| {
+            let (solution, energy) = f(sample.solution, sample.energy);
+
+            Sample {
+                solution,
+                energy,
+                occurrences: sample.occurrences,
+                replicas: sample.replicas,
+            }
+        }))
+    }
+
+    /// The samples that share the lowest energy
+    pub fn ground_states(&self) -> impl Iterator<Item=&Sample<S, E>> {
+        let ground_energy = self.samples.first().map(|sample| &sample.energy);
+
+        self.samples.iter().take_while(move |sample| Some(&sample.energy) == ground_energy)
+    }
+}
+
+impl<S, E> SampleSet<S, E> {
+    /// The sample with the lowest energy, unless the set is empty
+    pub fn best(&self) -> Option<&Sample<S, E>> {
+        self.samples.first()
+    }
+
+    /// The number of distinct solutions
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// The total number of solutions returned, counting repeated ones
+    pub fn nb_occurrences(&self) -> usize {
+        self.samples.iter().map(|sample| sample.occurrences).sum()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Sample<S, E>> {
+        self.samples.iter()
+    }
+}
+
+/// Collects `(solution, energy, replica)` triplets
+impl<S, E> FromIterator<(S, E, usize)> for SampleSet<S, E>
+    where
+        S: Eq + Hash,
+        E: PartialOrd,
+{
+    fn from_iter<I: IntoIterator<Item=(S, E, usize)>>(iter: I) -> Self {
+        Self::from_samples(iter.into_iter().map(|(solution, energy, replica)| Sample {
+            solution,
+            energy,
+            occurrences: 1,
+            replicas: vec![replica],
+        }))
+    }
+}
+
+impl<S, E> IntoIterator for SampleSet<S, E> {
+    type Item = Sample<S, E>;
+    type IntoIter = std::vec::IntoIter<Sample<S, E>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.samples.into_iter()
+    }
+}
+
+impl<'a, S, E> IntoIterator for &'a SampleSet<S, E> {
+    type Item = &'a Sample<S, E>;
+    type IntoIter = std::slice::Iter<'a, Sample<S, E>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.samples.iter()
+    }
+}
+
+/// A flag that can be shared with a running solver to ask it to stop early
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Conditions under which a solver should give up and return the best solution it has found.
+/// A solver always stops when it finishes on its own, so the default criteria never fire.
+#[derive(Clone, Debug)]
+pub struct StoppingCriteria<E> {
+    pub time_limit: Option<Duration>,
+    pub target_energy: Option<E>,
+    pub max_evaluations: Option<usize>,
+    pub cancellation_token: Option<CancellationToken>,
+}
+
+impl<E> Default for StoppingCriteria<E> {
+    fn default() -> Self {
+        Self {
+            time_limit: None,
+            target_energy: None,
+            max_evaluations: None,
+            cancellation_token: None,
+        }
+    }
+}
+
+impl<E> StoppingCriteria<E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_time_limit(self, time_limit: Duration) -> Self {
+        Self { time_limit: Some(time_limit), ..self }
+    }
+
+    pub fn with_target_energy(self, target_energy: E) -> Self {
+        Self { target_energy: Some(target_energy), ..self }
+    }
+
+    pub fn with_max_evaluations(self, max_evaluations: usize) -> Self {
+        Self { max_evaluations: Some(max_evaluations), ..self }
+    }
+
+    pub fn with_cancellation_token(self, cancellation_token: CancellationToken) -> Self {
+        Self { cancellation_token: Some(cancellation_token), ..self }
+    }
+
+    /// Starts the clock for a single solver run
+    pub fn start(&self) -> StoppingCondition<'_, E> {
+        StoppingCondition {
+            criteria: self,
+            start: Instant::now(),
+            evaluations: AtomicUsize::new(0),
+            stopped: AtomicBool::new(false),
+        }
+    }
+}
+
+/// The state of the stopping criteria during a solver run. This is shared between all the threads
+/// of a solver, so once any thread decides to stop every other thread will stop as well.
+pub struct StoppingCondition<'a, E> {
+    criteria: &'a StoppingCriteria<E>,
+    start: Instant,
+    evaluations: AtomicUsize,
+    stopped: AtomicBool,
+}
+
+impl<E> StoppingCondition<'_, E>
+    where
+        E: PartialOrd,
+{
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Records that `count` more solutions have been evaluated
+    #[inline]
+    pub fn record_evaluations(&self, count: usize) {
+        if self.criteria.max_evaluations.is_some() {
+            self.evaluations.fetch_add(count, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns true if the run has already been stopped, without checking any of the criteria
+    #[inline]
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Checks all of the stopping criteria against the best energy the caller has found so far.
+    /// Once this has returned true it will always return true.
+    pub fn should_stop(&self, best_energy: &E) -> bool {
+        if self.is_stopped() {
+            return true;
+        }
+
+        let criteria = self.criteria;
+
+        let should_stop = criteria
+            .target_energy
+            .as_ref()
+            .is_some_and(|target| best_energy <= target)
+            || criteria
+            .max_evaluations
+            .is_some_and(|max| self.evaluations.load(Ordering::Relaxed) >= max)
+            || criteria
+            .cancellation_token
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+            || criteria.time_limit.is_some_and(|limit| self.elapsed() >= limit);
+
+        if should_stop {
+            self.stopped.store(true, Ordering::Relaxed);
+        }
+
+        should_stop
+    }
 }
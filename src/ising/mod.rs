@@ -0,0 +1,7 @@
+pub use problem::*;
+
+mod problem;
+pub mod reductions;
+
+#[cfg(test)]
+mod test;
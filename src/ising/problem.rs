@@ -0,0 +1,181 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::iter::zip;
+
+use nalgebra::{DMatrix, DVector};
+use nalgebra_sparse::{CooMatrix, CsrMatrix, SparseFormatError};
+use thiserror::Error;
+
+use crate::core::Problem;
+use crate::qubo::{QuboSolution, QuboType, QuboWeight};
+
+/// A single spin, either `1` or `-1`
+pub type Spin = i8;
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[repr(transparent)]
+pub struct IsingSolution(pub DVector<Spin>);
+
+impl Display for IsingSolution {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for s in self.0.iter() {
+            write!(f, "{}", if *s > 0 { '+' } else { '-' })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl IsingSolution {
+    pub fn flip(&self, i: usize) -> Self {
+        let mut temp_solution = self.clone();
+
+        temp_solution.0[i] = -temp_solution.0[i];
+
+        temp_solution
+    }
+}
+
+/// Maps the bits `x` onto the spins `s = 2x - 1`
+impl From<QuboSolution> for IsingSolution {
+    fn from(solution: QuboSolution) -> Self {
+        IsingSolution(DVector::from_fn(solution.len(), |i, _| if solution.get(i) { 1 } else { -1 }))
+    }
+}
+
+/// Maps the spins `s` onto the bits `x = (s + 1) / 2`
+impl From<IsingSolution> for QuboSolution {
+    fn from(IsingSolution(spins): IsingSolution) -> Self {
+        QuboSolution::from_fn(spins.len(), |i| spins[i] > 0)
+    }
+}
+
+/// An Ising model with the energy `E(s) = sum_{i<j} J_ij s_i s_j + sum_i h_i s_i + offset`.
+///
+/// `J` is stored symmetrically, so that every row holds all the couplings of a spin.
+pub struct IsingProblem<T = QuboType> {
+    couplings: CsrMatrix<T>,
+    biases: DVector<T>,
+    offset: T,
+}
+
+impl<T> Problem for IsingProblem<T> {
+    type Solution = IsingSolution;
+    type Energy = T;
+}
+
+#[derive(Error, Debug)]
+pub enum IsingError {
+    #[error("The provided J Matrix has an invalid triplet: {0}")]
+    InvalidTriplets(#[from] SparseFormatError),
+    #[error("The bias of spin {0} is outside of the problem")]
+    InvalidBias(usize),
+}
+
+impl<T> IsingProblem<T>
+    where
+        T: QuboWeight,
+{
+    /// Builds a problem from couplings and biases, summing any duplicates. A coupling may be given as
+    /// either `(i, j)` or `(j, i)`, and couplings of a spin with itself are constant, as `s_i^2 = 1`, so
+    /// they are added to the offset.
+    pub fn try_from_triplets(
+        problem_size: usize,
+        j_triplets: impl IntoIterator<Item=(usize, usize, T)>,
+        h_biases: impl IntoIterator<Item=(usize, T)>,
+        mut offset: T,
+    ) -> Result<Self, IsingError> {
+        let (mut row_indices, mut col_indices, mut values) = (Vec::new(), Vec::new(), Vec::new());
+
+        for (i, j, v) in j_triplets {
+            if i == j && i < problem_size {
+                offset += v;
+                continue;
+            }
+
+            row_indices.extend([i, j]);
+            col_indices.extend([j, i]);
+            values.extend([v, v]);
+        }
+
+        let couplings = CooMatrix::try_from_triplets(problem_size, problem_size, row_indices, col_indices, values)?;
+
+        let mut biases = DVector::zeros(problem_size);
+        for (i, h) in h_biases {
+            *biases.get_mut(i).ok_or(IsingError::InvalidBias(i))? += h;
+        }
+
+        Ok(IsingProblem {
+            couplings: CsrMatrix::from(&couplings),
+            biases,
+            offset,
+        })
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.biases.len()
+    }
+
+    /// The symmetric coupling matrix `J`
+    pub fn get_couplings(&self) -> &CsrMatrix<T> {
+        &self.couplings
+    }
+
+    pub fn get_biases(&self) -> &DVector<T> {
+        &self.biases
+    }
+
+    pub fn get_offset(&self) -> T {
+        self.offset
+    }
+
+    pub fn evaluate(&self, IsingSolution(spins): &IsingSolution) -> T {
+        let bias_energy: T = zip(self.biases.iter(), spins.iter())
+            .map(|(&h, &s)| if s > 0 { h } else { -h })
+            .sum();
+
+        let coupling_energy: T = self
+            .couplings
+            .triplet_iter()
+            .filter(|(i, j, _)| i < j)
+            .map(|(i, j, &v)| if spins[i] == spins[j] { v } else { -v })
+            .sum();
+
+        bias_energy + coupling_energy + self.offset
+    }
+
+    /// Calculate the delta between a solution and the next solution by flipping spin k.
+    /// This operation is linear in the number of couplings of k.
+    pub fn delta_evaluate_k(&self, IsingSolution(spins): &IsingSolution, k: usize) -> T {
+        let row = self.couplings.get_row(k).expect("K should not be out of bounds!");
+
+        let local_field = zip(row.col_indices(), row.values())
+            .map(|(&j, &v)| if spins[j] > 0 { v } else { -v })
+            .fold(self.biases[k], |acc, x| acc + x);
+
+        // Flipping s_k negates every term containing it, so the energy changes by -2 s_k f_k
+        let two_local_field = local_field + local_field;
+
+        if spins[k] > 0 {
+            -two_local_field
+        } else {
+            two_local_field
+        }
+    }
+}
+
+/// Note: This operation is expensive, only print if ABSOLUTELY necessary
+impl<T> Debug for IsingProblem<T>
+    where
+        T: QuboWeight,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "IsingProblem of size {} with offset {}{}{}",
+            self.get_size(),
+            self.offset,
+            DMatrix::from(&self.couplings),
+            self.biases.transpose()
+        )
+    }
+}
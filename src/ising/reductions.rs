@@ -0,0 +1,111 @@
+use crate::core::{DownModel, Reduction};
+use crate::ising::{IsingProblem, IsingSolution};
+use crate::qubo::{QuboProblem, QuboSolution, QuboWeight};
+
+/// Maps bits onto spins with `s = 2x - 1`. The Ising energy is exactly twice the QUBO energy, which
+/// keeps the coefficients integral.
+pub struct QuboToIsing;
+
+impl<T> Reduction<QuboProblem<T>, IsingProblem<T>> for QuboToIsing
+    where
+        T: QuboWeight,
+{
+    fn reduce(qubo_problem: &QuboProblem<T>) -> (IsingProblem<T>, Self) {
+        let problem_size = qubo_problem.get_size();
+        let q_matrix = qubo_problem.get_sparse();
+
+        // With x_i = (1 + s_i) / 2, every pair i < j contributes q_ij (1 + s_i + s_j + s_i s_j) and every
+        // q_ii contributes q_ii (1 + s_i) to twice the energy, so h is the row sum and the offset is the
+        // sum of the upper triangle
+        let biases = q_matrix
+            .row_iter()
+            .enumerate()
+            .map(|(i, row)| (i, row.values().iter().cloned().sum::<T>()));
+
+        let offset = q_matrix
+            .triplet_iter()
+            .filter(|(i, j, _)| i <= j)
+            .map(|(_, _, &v)| v)
+            .sum();
+
+        let couplings = q_matrix
+            .triplet_iter()
+            .filter(|(i, j, _)| i < j)
+            .map(|(i, j, &v)| (i, j, v));
+
+        let ising_problem = IsingProblem::try_from_triplets(problem_size, couplings, biases, offset)
+            .expect("Matrix should be properly constructed.");
+
+        (ising_problem, Self)
+    }
+
+    fn up_model(&self, solution: IsingSolution) -> QuboSolution {
+        solution.into()
+    }
+}
+
+impl<T> DownModel<QuboProblem<T>, IsingProblem<T>> for QuboToIsing
+    where
+        T: QuboWeight,
+{
+    fn down_model(&self, _: &IsingProblem<T>, solution: QuboSolution) -> IsingSolution {
+        solution.into()
+    }
+}
+
+/// Maps spins onto bits with `x = (s + 1) / 2`. The Ising energy is half the QUBO energy plus the
+/// [offset](IsingToQubo::offset).
+pub struct IsingToQubo<T> {
+    offset: T,
+}
+
+impl<T> IsingToQubo<T>
+    where
+        T: Copy,
+{
+    pub fn offset(&self) -> T {
+        self.offset
+    }
+}
+
+impl<T> Reduction<IsingProblem<T>, QuboProblem<T>> for IsingToQubo<T>
+    where
+        T: QuboWeight,
+{
+    fn reduce(ising_problem: &IsingProblem<T>) -> (QuboProblem<T>, Self) {
+        let couplings = ising_problem
+            .get_couplings()
+            .triplet_iter()
+            .filter(|(i, j, _)| i < j)
+            .map(|(i, j, &v)| (i, j, v));
+
+        let biases = ising_problem
+            .get_biases()
+            .iter()
+            .enumerate()
+            .map(|(i, &h)| (i, h));
+
+        let (qubo_problem, offset) = QuboProblem::try_from_ising_triplets(ising_problem.get_size(), couplings, biases)
+            .expect("Matrix should be properly constructed.");
+
+        (
+            qubo_problem,
+            Self {
+                offset: offset + ising_problem.get_offset(),
+            },
+        )
+    }
+
+    fn up_model(&self, solution: QuboSolution) -> IsingSolution {
+        solution.into()
+    }
+}
+
+impl<T> DownModel<IsingProblem<T>, QuboProblem<T>> for IsingToQubo<T>
+    where
+        T: QuboWeight,
+{
+    fn down_model(&self, _: &QuboProblem<T>, solution: IsingSolution) -> QuboSolution {
+        solution.into()
+    }
+}
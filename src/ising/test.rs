@@ -0,0 +1,145 @@
+use nalgebra::{DMatrix, DVector};
+use nalgebra_sparse::CsrMatrix;
+use rand::{Rng, SeedableRng, rngs::StdRng, thread_rng};
+
+use crate::core::{Reduction, Solver};
+use crate::ising::{IsingProblem, IsingSolution};
+use crate::ising::reductions::{IsingToQubo, QuboToIsing};
+use crate::qubo::{QuboProblem, QuboSolution};
+use crate::qubo::solvers::{ExhaustiveSearch, MomentumAnnealer};
+
+const PROBLEM_SIZE: usize = 30;
+
+fn momentum_annealer(max_iterations: usize) -> MomentumAnnealer {
+    MomentumAnnealer::new(
+        max_iterations,
+        MomentumAnnealer::default_temperature(),
+        MomentumAnnealer::default_dropout(),
+        MomentumAnnealer::default_momentum_scaling(),
+    )
+}
+
+fn random_spins(rng: &mut impl Rng, problem_size: usize) -> IsingSolution {
+    IsingSolution(DVector::from_fn(problem_size, |_, _| if rng.gen_bool(0.5) { 1 } else { -1 }))
+}
+
+#[test]
+fn check_evaluation() {
+    // E = 2 s_0 s_1 - 3 s_1 s_2 + s_0 - 2 s_2 + 5, where the (1, 1) coupling is constant
+    let problem = IsingProblem::try_from_triplets(3, vec![(0, 1, 1), (1, 0, 1), (2, 1, -3), (1, 1, 4)], vec![(0, 1), (2, -2)], 1)
+        .unwrap();
+
+    assert_eq!(problem.evaluate(&IsingSolution(DVector::from_vec(vec![1, 1, 1]))), 2 - 3 + 1 - 2 + 5);
+    assert_eq!(problem.evaluate(&IsingSolution(DVector::from_vec(vec![1, -1, -1]))), -2 - 3 + 1 + 2 + 5);
+
+    assert!(IsingProblem::try_from_triplets(3, vec![(0, 3, 1)], vec![], 0).is_err());
+    assert!(IsingProblem::try_from_triplets(3, vec![], vec![(3, 1)], 0).is_err());
+}
+
+#[test]
+fn check_delta_evaluation_k() {
+    let mut rng = thread_rng();
+
+    let triplets: Vec<_> = (0..4 * PROBLEM_SIZE)
+        .map(|_| (rng.gen_range(0..PROBLEM_SIZE), rng.gen_range(0..PROBLEM_SIZE), rng.gen_range(-64..64)))
+        .collect();
+    let biases: Vec<_> = (0..PROBLEM_SIZE).map(|i| (i, rng.gen_range(-64..64))).collect();
+    let problem = IsingProblem::try_from_triplets(PROBLEM_SIZE, triplets, biases, 7).unwrap();
+
+    let solution = random_spins(&mut rng, PROBLEM_SIZE);
+    let eval = problem.evaluate(&solution);
+
+    for k in 0..PROBLEM_SIZE {
+        assert_eq!(problem.evaluate(&solution.flip(k)) - eval, problem.delta_evaluate_k(&solution, k));
+    }
+}
+
+#[test]
+fn check_qubo_to_ising() {
+    let mut rng = thread_rng();
+
+    let q_matrix = CsrMatrix::from(&DMatrix::from_fn(PROBLEM_SIZE, PROBLEM_SIZE, |_, _| {
+        if rng.gen_bool(0.3) { rng.gen_range(-64..64) } else { 0 }
+    }));
+    let qubo_problem = QuboProblem::try_from_q_matrix(q_matrix).unwrap();
+
+    let (ising_problem, reducer) = QuboToIsing::reduce(&qubo_problem);
+
+    for _ in 0..100 {
+        let solution = random_spins(&mut rng, PROBLEM_SIZE);
+        let qubo_solution: QuboSolution = Reduction::<QuboProblem, IsingProblem>::up_model(&reducer, solution.clone());
+
+        assert_eq!(ising_problem.evaluate(&solution), 2 * qubo_problem.evaluate(&qubo_solution));
+    }
+}
+
+#[test]
+fn check_ising_to_qubo() {
+    let mut rng = thread_rng();
+
+    let triplets: Vec<_> = (0..4 * PROBLEM_SIZE)
+        .map(|_| (rng.gen_range(0..PROBLEM_SIZE), rng.gen_range(0..PROBLEM_SIZE), rng.gen_range(-64..64)))
+        .collect();
+    let biases: Vec<_> = (0..PROBLEM_SIZE).map(|i| (i, rng.gen_range(-64..64))).collect();
+    let ising_problem = IsingProblem::try_from_triplets(PROBLEM_SIZE, triplets, biases, -11).unwrap();
+
+    let (qubo_problem, reducer) = IsingToQubo::reduce(&ising_problem);
+
+    for _ in 0..100 {
+        let qubo_solution = QuboSolution::from(DVector::from_fn(PROBLEM_SIZE, |_, _| rng.gen_range(0..=1)));
+        let solution = reducer.up_model(qubo_solution.clone());
+
+        assert_eq!(ising_problem.evaluate(&solution), qubo_problem.evaluate(&qubo_solution) / 2 + reducer.offset());
+    }
+}
+
+#[test]
+fn check_momentum_annealing() {
+    const SMALL_PROBLEM_SIZE: usize = 8;
+    const SEED: u64 = 21;
+
+    let mut rng = StdRng::seed_from_u64(SEED);
+
+    let triplets: Vec<_> = (0..SMALL_PROBLEM_SIZE)
+        .flat_map(|i| (i + 1..SMALL_PROBLEM_SIZE).map(move |j| (i, j)))
+        .map(|(i, j)| (i, j, rng.gen_range(-8..8)))
+        .collect();
+    let ising_problem = IsingProblem::try_from_triplets(SMALL_PROBLEM_SIZE, triplets, vec![], 0).unwrap();
+    let (qubo_problem, reducer) = IsingToQubo::reduce(&ising_problem);
+
+    let optimum = ising_problem.evaluate(&reducer.up_model(ExhaustiveSearch::new().solve(&qubo_problem)));
+
+    let solution = momentum_annealer(1_000).with_seed(SEED).solve(&ising_problem);
+    let qubo_solution = momentum_annealer(1_000).with_seed(SEED).solve(&qubo_problem);
+
+    assert_eq!(optimum, ising_problem.evaluate(&solution));
+    assert_eq!(optimum, qubo_problem.evaluate(&qubo_solution) / 2 + reducer.offset());
+}
+
+#[test]
+fn check_sparse_momentum_annealing() {
+    const LARGE_PROBLEM_SIZE: usize = 2_000;
+    const SEED: u64 = 21;
+
+    let mut rng = StdRng::seed_from_u64(SEED);
+
+    // A chain is never frustrated, so its ground state satisfies every coupling
+    let triplets: Vec<(usize, usize, i32)> = (0..LARGE_PROBLEM_SIZE - 1)
+        .map(|i| (i, i + 1, if rng.gen_bool(0.5) { rng.gen_range(1..8) } else { -rng.gen_range(1..8) }))
+        .collect();
+    let optimum: i32 = -triplets.iter().map(|(_, _, v)| v.abs()).sum::<i32>();
+    let ising_problem = IsingProblem::try_from_triplets(LARGE_PROBLEM_SIZE, triplets, vec![], 0).unwrap();
+
+    let solution = momentum_annealer(1_000).with_seed(SEED).solve(&ising_problem);
+
+    // The parallel updates leave a few domain walls behind
+    assert!(ising_problem.evaluate(&solution) <= optimum * 9 / 10);
+
+    // Without noise every spin follows its local field, which satisfies most of the couplings, whereas
+    // a random assignment only satisfies half of them
+    let solution = MomentumAnnealer::new(1_000, |_| 0.0, MomentumAnnealer::default_dropout(), MomentumAnnealer::default_momentum_scaling())
+        .with_seed(SEED)
+        .solve(&ising_problem);
+
+    assert!(ising_problem.evaluate(&solution) <= optimum / 2);
+}
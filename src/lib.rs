@@ -1,6 +1,9 @@
 #[cfg(feature = "qubo")]
 pub mod qubo;
 
+#[cfg(feature = "qubo")]
+pub mod ising;
+
 #[cfg(feature = "sat")]
 pub mod sat;
 
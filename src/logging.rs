@@ -1,9 +1,158 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use log::info;
 
-struct SolverPerformance;
+use log::{info, warn};
 
-pub fn log_solver_performance<T>(solution_quality: T, iteration: usize) where T: Debug {
-    info!(target: "solver_performance", solution_quality:?, iteration:%; "Solution of quality {solution_quality:?} recorded at iteration step {iteration}")
-}
\ No newline at end of file
+/// A snapshot of the progress of a single replica of a solver
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolverEvent<E> {
+    pub iteration: usize,
+    pub energy: E,
+    pub best_energy: E,
+    pub replica: usize,
+    pub elapsed: Duration,
+}
+
+/// Receives progress events from solvers. Replicas run in parallel, so observers may be called from
+/// several threads at once.
+pub trait SolverObserver<E>: Send + Sync {
+    fn observe(&self, event: &SolverEvent<E>);
+}
+
+/// The set of observers attached to a solver
+pub struct Observers<E>(Vec<Arc<dyn SolverObserver<E>>>);
+
+impl<E> Default for Observers<E> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<E> Observers<E> {
+    pub fn push(&mut self, observer: Arc<dyn SolverObserver<E>>) {
+        self.0.push(observer)
+    }
+
+    /// Only builds the event if somebody is listening, as measuring the elapsed time is not free
+    #[inline]
+    pub fn notify_with(&self, event: impl FnOnce() -> SolverEvent<E>) {
+        if !self.0.is_empty() {
+            let event = event();
+
+            for observer in &self.0 {
+                observer.observe(&event);
+            }
+        }
+    }
+}
+
+/// Forwards events to the `log` facade under the `solver_performance` target
+pub struct LogObserver;
+
+impl<E> SolverObserver<E> for LogObserver
+    where
+        E: Debug,
+{
+    fn observe(&self, SolverEvent { iteration, energy, best_energy, replica, elapsed }: &SolverEvent<E>) {
+        info!(target: "solver_performance", replica:%, iteration:%, energy:?, best_energy:?, elapsed:?; "Replica {replica} recorded energy {energy:?} (best {best_energy:?}) at iteration step {iteration} after {elapsed:?}")
+    }
+}
+
+/// Writes events as CSV rows, starting with a header row
+pub struct CsvObserver<W>(Mutex<W>);
+
+impl<W> CsvObserver<W>
+    where
+        W: Write,
+{
+    pub fn new(mut writer: W) -> std::io::Result<Self> {
+        writeln!(writer, "replica,iteration,elapsed,energy,best_energy")?;
+
+        Ok(Self(Mutex::new(writer)))
+    }
+}
+
+impl<E, W> SolverObserver<E> for CsvObserver<W>
+    where
+        E: Display,
+        W: Write + Send,
+{
+    fn observe(&self, SolverEvent { iteration, energy, best_energy, replica, elapsed }: &SolverEvent<E>) {
+        let mut writer = self.0.lock().expect("Observer lock should not be poisoned");
+
+        if let Err(e) = writeln!(writer, "{replica},{iteration},{},{energy},{best_energy}", elapsed.as_secs_f64()) {
+            warn!("Failed to write solver event: {e}");
+        }
+    }
+}
+
+/// Writes every event as a JSON object on its own line. Energies that are NaN or infinite have no
+/// JSON literal, so they are written as `null`.
+pub struct JsonLinesObserver<W>(Mutex<W>);
+
+fn json_number(value: &impl Display) -> String {
+    match value.to_string() {
+        text if ["NaN", "inf", "-inf"].contains(&text.as_str()) => "null".to_string(),
+        text => text,
+    }
+}
+
+impl<W> JsonLinesObserver<W> {
+    pub fn new(writer: W) -> Self {
+        Self(Mutex::new(writer))
+    }
+}
+
+impl<E, W> SolverObserver<E> for JsonLinesObserver<W>
+    where
+        E: Display,
+        W: Write + Send,
+{
+    fn observe(&self, SolverEvent { iteration, energy, best_energy, replica, elapsed }: &SolverEvent<E>) {
+        let mut writer = self.0.lock().expect("Observer lock should not be poisoned");
+
+        if let Err(e) = writeln!(
+            writer,
+            r#"{{"replica":{replica},"iteration":{iteration},"elapsed":{},"energy":{},"best_energy":{}}}"#,
+            elapsed.as_secs_f64(),
+            json_number(energy),
+            json_number(best_energy),
+        ) {
+            warn!("Failed to write solver event: {e}");
+        }
+    }
+}
+
+/// Keeps every event in memory so they can be inspected after the solver has finished
+pub struct MemoryObserver<E>(Mutex<Vec<SolverEvent<E>>>);
+
+impl<E> Default for MemoryObserver<E> {
+    fn default() -> Self {
+        Self(Mutex::new(Vec::new()))
+    }
+}
+
+impl<E> MemoryObserver<E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes and returns all the events recorded so far
+    pub fn take_events(&self) -> Vec<SolverEvent<E>> {
+        std::mem::take(&mut self.0.lock().expect("Observer lock should not be poisoned"))
+    }
+}
+
+impl<E> SolverObserver<E> for MemoryObserver<E>
+    where
+        E: Clone + Send,
+{
+    fn observe(&self, event: &SolverEvent<E>) {
+        self.0
+            .lock()
+            .expect("Observer lock should not be poisoned")
+            .push(event.clone())
+    }
+}
@@ -1,4 +1,5 @@
 pub use problem::*;
 
 mod problem;
+pub mod reductions;
 pub mod solvers;
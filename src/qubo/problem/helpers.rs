@@ -1,11 +1,11 @@
 use crate::qubo::{QuboSolution, QuboType};
 
 // #[inline]
-// pub fn not(QuboSolution(solution_vector) : &QuboSolution, k : usize) -> QuboType {
-//     1 - solution_vector[k]
+// pub fn not(solution : &QuboSolution, k : usize) -> QuboType {
+//     1 - solution.value(k)
 // }
 
 #[inline]
-pub fn sigma(QuboSolution(solution_vector): &QuboSolution, k: usize) -> QuboType {
-    2 * solution_vector[k] - 1
+pub fn sigma(solution: &QuboSolution, k: usize) -> QuboType {
+    2 * solution.value(k) - 1
 }
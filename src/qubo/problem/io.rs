@@ -0,0 +1,312 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::str::FromStr;
+
+use log::warn;
+use thiserror::Error;
+
+use crate::qubo::{QuboError, QuboProblem, QuboWeight};
+
+#[derive(Error, Debug)]
+pub enum QuboFileError {
+    #[error("Error reading or writing the file")]
+    FileError(#[from] std::io::Error),
+    #[error("File missing problem header")]
+    MissingHeader,
+    #[error("Incorrect problem header on line {line}")]
+    InvalidHeader { line: usize },
+    #[error("Invalid entry on line {line}")]
+    InvalidEntry { line: usize },
+    #[error("Entry on line {line} refers to a variable outside of the problem")]
+    InvalidIndex { line: usize },
+    #[error("File ended before the end of the problem")]
+    UnexpectedEnd,
+    #[error("The coefficient {0} cannot be written exactly in this format")]
+    Unrepresentable(String),
+    #[error(transparent)]
+    InvalidProblem(#[from] QuboError),
+}
+
+/// The lines of a file that are not blank or comments, along with their line numbers
+fn content_lines(file: impl Read, is_comment: impl Fn(&str) -> bool) -> impl Iterator<Item=Result<(usize, String), QuboFileError>> {
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .map(|(i, line)| line.map(|line| (i + 1, line)).map_err(QuboFileError::from))
+        .filter(move |line| match line {
+            Ok((_, line)) => !(line.trim().is_empty() || is_comment(line.trim_start())),
+            Err(_) => true,
+        })
+}
+
+fn parse_fields<T>(fields: &[&str], error: impl Fn() -> QuboFileError) -> Result<Vec<T>, QuboFileError>
+    where
+        T: FromStr,
+{
+    fields.iter().map(|x| x.parse().or(Err(error()))).collect()
+}
+
+/// Parses the next whitespace separated token, along with the line it was found on
+fn next_value<U>(
+    tokens: &mut impl Iterator<Item=Result<(usize, String), QuboFileError>>,
+    error: impl Fn(usize) -> QuboFileError,
+) -> Result<(usize, U), QuboFileError>
+    where
+        U: FromStr,
+{
+    let (line, token) = tokens.next().ok_or(QuboFileError::UnexpectedEnd)??;
+
+    token.parse().map(|x| (line, x)).or(Err(error(line)))
+}
+
+/// Converts 1-based indices to 0-based ones, checking that they fit in the problem
+fn one_based((i, j): (usize, usize), problem_size: usize, line: usize) -> Result<(usize, usize), QuboFileError> {
+    match (i.checked_sub(1), j.checked_sub(1)) {
+        (Some(i), Some(j)) if i < problem_size && j < problem_size => Ok((i, j)),
+        _ => Err(QuboFileError::InvalidIndex { line }),
+    }
+}
+
+// The largest problem a file may declare. The matrix is sized from the header alone, so this bounds
+// what a malformed header can make the readers allocate.
+const MAX_PROBLEM_SIZE: usize = 1 << 24;
+
+/// Rejects a problem size from the header on line `line` that is above [MAX_PROBLEM_SIZE]
+fn check_problem_size(problem_size: usize, line: usize) -> Result<usize, QuboFileError> {
+    if problem_size > MAX_PROBLEM_SIZE {
+        return Err(QuboFileError::InvalidHeader { line });
+    }
+
+    Ok(problem_size)
+}
+
+impl<T> QuboProblem<T>
+    where
+        T: QuboWeight,
+{
+    /// The coefficients of the upper triangular `Q` with `E(x) = 2 x^T Q x`, i.e. the form used by
+    /// most file formats. Zero coefficients are skipped.
+    fn upper_triangle_q(&self) -> impl Iterator<Item=(usize, usize, T)> + '_ {
+        let two = T::one() + T::one();
+
+        self.0
+            .triplet_iter()
+            .filter(|(i, j, v)| i <= j && !v.is_zero())
+            .map(move |(i, j, &v)| if i == j { (i, j, v / two) } else { (i, j, v) })
+    }
+
+    /// Writes the problem in the qbsolv `.qubo` format, with 0-based indices
+    pub fn write_qbsolv(&self, mut writer: impl Write) -> Result<(), QuboFileError> {
+        let (diagonal, couplers): (Vec<_>, Vec<_>) = self.upper_triangle_q().partition(|(i, j, _)| i == j);
+
+        writeln!(writer, "p qubo 0 {} {} {}", self.get_size(), diagonal.len(), couplers.len())?;
+        for (i, j, v) in diagonal.into_iter().chain(couplers) {
+            writeln!(writer, "{} {} {}", i, j, v)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the problem as a general Matrix Market coordinate matrix of the upper triangle of `Q`
+    pub fn write_matrix_market(&self, mut writer: impl Write) -> Result<(), QuboFileError> {
+        let entries: Vec<_> = self.upper_triangle_q().collect();
+
+        writeln!(writer, "%%MatrixMarket matrix coordinate real general")?;
+        writeln!(writer, "{} {} {}", self.get_size(), self.get_size(), entries.len())?;
+        for (i, j, v) in entries {
+            writeln!(writer, "{} {} {}", i + 1, j + 1, v)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the problem as a single ORLib problem. See [QuboProblem::from_bqp_file] for how the
+    /// coefficients are interpreted.
+    pub fn write_bqp(&self, mut writer: impl Write) -> Result<(), QuboFileError> {
+        let two = T::one() + T::one();
+
+        let entries = self
+            .upper_triangle_q()
+            .map(|(i, j, v)| {
+                let q_ij = if i == j { v } else { v / two };
+
+                // Odd integer couplers cannot be split evenly between q_ij and q_ji
+                if i != j && q_ij + q_ij != v {
+                    Err(QuboFileError::Unrepresentable(v.to_string()))
+                } else {
+                    Ok((i, j, -q_ij))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        writeln!(writer, "1")?;
+        writeln!(writer, "{} {}", self.get_size(), entries.len())?;
+        for (i, j, v) in entries {
+            writeln!(writer, "{} {} {}", i + 1, j + 1, v)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> QuboProblem<T>
+    where
+        T: QuboWeight + FromStr,
+{
+    /// Reads the qbsolv `.qubo` format: a `p qubo topology max_nodes nb_nodes nb_couplers` header
+    /// followed by `i j value` entries with 0-based indices, where the energy is the sum of
+    /// `value * x_i * x_j` over all the entries.
+    pub fn from_qbsolv_file(file: impl Read) -> Result<Self, QuboFileError> {
+        let mut lines = content_lines(file, |line| line.starts_with('c'));
+
+        let (header_line, header) = lines.next().ok_or(QuboFileError::MissingHeader)??;
+        let header_error = || QuboFileError::InvalidHeader { line: header_line };
+        let fields: Vec<&str> = header.split_whitespace().collect();
+        let (problem_size, nb_entries) = match fields.as_slice() {
+            ["p", "qubo", _, sizes @ ..] if sizes.len() == 3 => {
+                let sizes: Vec<usize> = parse_fields(sizes, header_error)?;
+
+                (
+                    check_problem_size(sizes[0], header_line)?,
+                    sizes[1].checked_add(sizes[2]).ok_or_else(header_error)?,
+                )
+            }
+            _ => return Err(header_error()),
+        };
+
+        // The header is not trusted with the size of any buffer
+        let mut triplets = Vec::new();
+        for next_line in lines {
+            let (line, entry) = next_line?;
+            let error = || QuboFileError::InvalidEntry { line };
+
+            let [i, j, v] = entry.split_whitespace().collect::<Vec<_>>()[..] else {
+                return Err(error());
+            };
+            let (i, j): (usize, usize) = (i.parse().or(Err(error()))?, j.parse().or(Err(error()))?);
+
+            if i >= problem_size || j >= problem_size {
+                return Err(QuboFileError::InvalidIndex { line });
+            }
+
+            triplets.push((i, j, v.parse().or(Err(error()))?));
+        }
+
+        if triplets.len() != nb_entries {
+            warn!("Header declares {} entries but {} were found", nb_entries, triplets.len());
+        }
+
+        Ok(Self::try_from_triplets(problem_size, triplets)?)
+    }
+
+    /// Reads a square Matrix Market coordinate matrix `M`, with an energy of `x^T M x`. Symmetric
+    /// matrices only store their lower triangle, so their off diagonal entries are counted twice.
+    pub fn from_matrix_market_file(file: impl Read) -> Result<Self, QuboFileError> {
+        let mut lines = BufReader::new(file)
+            .lines()
+            .enumerate()
+            .map(|(i, line)| line.map(|line| (i + 1, line)));
+
+        let (line, banner) = lines.next().ok_or(QuboFileError::MissingHeader)??;
+        let banner = banner.to_lowercase();
+        let (is_pattern, is_symmetric) = match banner.split_whitespace().collect::<Vec<_>>()[..] {
+            ["%%matrixmarket", "matrix", "coordinate", field, symmetry]
+            if ["real", "integer", "pattern"].contains(&field) && ["general", "symmetric"].contains(&symmetry) => {
+                (field == "pattern", symmetry == "symmetric")
+            }
+            _ => return Err(QuboFileError::InvalidHeader { line }),
+        };
+
+        let mut lines = lines
+            .map(|line| line.map_err(QuboFileError::from))
+            .filter(|line| !matches!(line, Ok((_, line)) if line.trim().is_empty() || line.starts_with('%')));
+
+        let (header_line, sizes) = lines.next().ok_or(QuboFileError::MissingHeader)??;
+        let [nb_rows, nb_cols, nb_entries] = parse_fields(
+            &sizes.split_whitespace().collect::<Vec<_>>(),
+            || QuboFileError::InvalidHeader { line: header_line },
+        )?[..] else {
+            return Err(QuboFileError::InvalidHeader { line: header_line });
+        };
+
+        if nb_rows != nb_cols {
+            return Err(QuboError::IncorrectSize.into());
+        }
+        check_problem_size(nb_rows, header_line)?;
+
+        let mut triplets = Vec::new();
+        for next_line in lines {
+            let (line, entry) = next_line?;
+            let error = || QuboFileError::InvalidEntry { line };
+
+            let fields: Vec<&str> = entry.split_whitespace().collect();
+            let (i, j, v) = match (fields.as_slice(), is_pattern) {
+                (&[i, j], true) => (i, j, T::one()),
+                (&[i, j, v], false) => (i, j, v.parse().or(Err(error()))?),
+                _ => return Err(error()),
+            };
+            let (i, j) = one_based((i.parse().or(Err(error()))?, j.parse().or(Err(error()))?), nb_rows, line)?;
+
+            triplets.push((i, j, v));
+            if is_symmetric && i != j {
+                triplets.push((j, i, v));
+            }
+        }
+
+        let nb_read = if is_symmetric { triplets.iter().filter(|(i, j, _)| i >= j).count() } else { triplets.len() };
+        if nb_read != nb_entries {
+            warn!("Header declares {} entries but {} were found", nb_entries, nb_read);
+        }
+
+        Ok(Self::try_from_triplets(nb_rows, triplets)?)
+    }
+
+    /// Reads every problem of an ORLib (Beasley) `bqp` file. Each problem lists the entries of a
+    /// symmetric matrix `Q` with 1-based indices, giving each off diagonal entry once. These are
+    /// maximisation problems of `x^T Q x`, so the coefficients are negated to turn them into
+    /// minimisation problems with the same optimal assignments.
+    pub fn from_bqp_file(file: impl Read) -> Result<Vec<Self>, QuboFileError> {
+        let mut tokens = BufReader::new(file)
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                line.map(|line| {
+                    line.split_whitespace()
+                        .map(|token| (i + 1, token.to_string()))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .flat_map(|line| match line {
+                Ok(tokens) => tokens.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(QuboFileError::from(e))],
+            });
+
+        let header_error = |line| QuboFileError::InvalidHeader { line };
+        let entry_error = |line| QuboFileError::InvalidEntry { line };
+
+        let (_, nb_problems): (_, usize) = next_value(&mut tokens, header_error)?;
+        let mut problems = Vec::new();
+
+        for _ in 0..nb_problems {
+            let (header_line, problem_size) = next_value(&mut tokens, header_error)?;
+            let problem_size = check_problem_size(problem_size, header_line)?;
+            let (_, nb_entries): (_, usize) = next_value(&mut tokens, header_error)?;
+
+            let mut triplets = Vec::new();
+            for _ in 0..nb_entries {
+                let (line, i) = next_value(&mut tokens, entry_error)?;
+                let (_, j) = next_value(&mut tokens, entry_error)?;
+                let (_, v): (_, T) = next_value(&mut tokens, entry_error)?;
+                let (i, j) = one_based((i, j), problem_size, line)?;
+
+                triplets.push((i, j, -v));
+                if i != j {
+                    triplets.push((j, i, -v));
+                }
+            }
+
+            problems.push(Self::try_from_triplets(problem_size, triplets)?);
+        }
+
+        Ok(problems)
+    }
+}
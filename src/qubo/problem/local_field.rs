@@ -0,0 +1,102 @@
+use std::cmp::Ordering;
+use std::iter::zip;
+use std::ops::Range;
+
+use crate::qubo::{QuboProblem, QuboSolution, QuboWeight};
+
+/// A solution along with its evaluation and the delta of flipping each of its bits, which are kept
+/// up to date as bits are flipped.
+///
+/// Flipping bit `i` only changes the deltas of the bits coupled to it, so a flip walks the row of
+/// `i` in the CSR matrix and takes time linear in its degree. The solution is updated in place.
+#[derive(Clone, Debug)]
+pub struct LocalFieldState<T> {
+    solution: QuboSolution,
+    evaluation: T,
+    deltas: Vec<T>,
+}
+
+impl<T> LocalFieldState<T>
+    where
+        T: QuboWeight,
+{
+    /// Evaluates the solution and all of its deltas, which takes time linear in the number of
+    /// entries of the problem
+    pub fn new(qubo_problem: &QuboProblem<T>, solution: QuboSolution) -> Self {
+        let evaluation = qubo_problem.evaluate(&solution);
+        let deltas = (0..qubo_problem.get_size())
+            .map(|i| qubo_problem.delta_evaluate_k(&solution, i))
+            .collect();
+
+        Self {
+            solution,
+            evaluation,
+            deltas,
+        }
+    }
+
+    pub fn solution(&self) -> &QuboSolution {
+        &self.solution
+    }
+
+    pub fn into_solution(self) -> QuboSolution {
+        self.solution
+    }
+
+    pub fn evaluation(&self) -> T {
+        self.evaluation
+    }
+
+    /// `E(flip(i, X)) - E(X)` for every bit `i`
+    pub fn deltas(&self) -> &[T] {
+        &self.deltas
+    }
+
+    pub fn delta(&self, i: usize) -> T {
+        self.deltas[i]
+    }
+
+    /// Flips bit `i`, updating the evaluation and the deltas of its neighbours
+    pub fn flip(&mut self, qubo_problem: &QuboProblem<T>, i: usize) {
+        self.flip_below(qubo_problem, i, qubo_problem.get_size());
+    }
+
+    /// Flips bit `i` like [LocalFieldState::flip], but only updates the deltas of the bits below
+    /// `limit`, besides its own. The other deltas are stale until the flip is undone with the same
+    /// limit, which lets a search that undoes its flips skip the bits it does not read.
+    pub fn flip_below(&mut self, qubo_problem: &QuboProblem<T>, i: usize, limit: usize) {
+        let row = qubo_problem.0.row(i);
+        let x_i = self.solution.get(i);
+
+        // Flipping i changes the delta of each neighbour j by 2 w_ij, with a sign that depends on
+        // whether both bits agree before the flip. The columns of a row are sorted.
+        for (&j, &w_ij) in zip(row.col_indices(), row.values()).take_while(|(j, _)| **j < limit) {
+            if j == i {
+                continue;
+            }
+
+            let two_w_ij = w_ij + w_ij;
+            if x_i == self.solution.get(j) {
+                self.deltas[j] += two_w_ij;
+            } else {
+                self.deltas[j] -= two_w_ij;
+            }
+        }
+
+        self.evaluation += self.deltas[i];
+        self.deltas[i] = -self.deltas[i];
+        self.solution.flip_in_place(i);
+    }
+
+    /// Flips the bit of `free` that lowers the evaluation the most until none does, which leaves the
+    /// solution at a local minimum over those bits
+    pub fn descend(&mut self, qubo_problem: &QuboProblem<T>, free: Range<usize>) {
+        while let Some(i) = free
+            .clone()
+            .filter(|i| self.deltas[*i] < T::zero())
+            .min_by(|a, b| self.deltas[*a].partial_cmp(&self.deltas[*b]).unwrap_or(Ordering::Equal))
+        {
+            self.flip(qubo_problem, i);
+        }
+    }
+}
@@ -1,48 +1,73 @@
 use std::fmt::{Debug, Display, Formatter};
-use std::iter::zip;
+use std::iter::{Sum, zip};
+use std::ops::{AddAssign, Neg, SubAssign};
 
-use nalgebra::{DMatrix, DVector};
+use nalgebra::{ClosedAdd, ClosedMul, ClosedSub, DMatrix, DVector, Scalar};
 use nalgebra_sparse::{CooMatrix, CsrMatrix, SparseFormatError};
+use num_traits::{Num, NumCast};
 use thiserror::Error;
 
 use crate::core::Problem;
 use crate::qubo::problem::helpers::sigma;
 
+pub use io::QuboFileError;
+pub use local_field::LocalFieldState;
+pub use solution::QuboSolution;
+
 #[cfg(test)]
 mod test;
 
 mod helpers;
+mod io;
+mod local_field;
+mod solution;
 
+/// The coefficient type used when none is specified, and the type of the entries of a solution vector
 pub type QuboType = i32;
 
-pub struct QuboProblem(CsrMatrix<QuboType>, usize);
-
-#[derive(Clone, Eq, PartialEq, Hash, Debug)]
-#[repr(transparent)]
-pub struct QuboSolution(pub DVector<QuboType>);
-
-impl Display for QuboSolution {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        for i in self.0.iter() {
-            write!(f, "{:1}", i)?;
-        }
-
-        Ok(())
-    }
-}
-
-impl QuboSolution {
-    pub fn flip(&self, i: usize) -> Self {
-        let mut temp_solution = self.clone();
-
-        temp_solution.0[i] = 1 - temp_solution.0[i];
-
-        temp_solution
-    }
-}
-
-impl Problem for QuboProblem {
+/// Any numeric type that can be used for the coefficients of a [QuboProblem], e.g. `i32`, `i64`
+/// or `f64`
+pub trait QuboWeight:
+Scalar
++ Copy
++ Num
++ NumCast
++ PartialOrd
++ Neg<Output=Self>
++ ClosedAdd
++ ClosedSub
++ ClosedMul
++ AddAssign
++ SubAssign
++ Sum
++ Send
++ Sync
++ Display
+{}
+
+impl<T> QuboWeight for T where
+    T: Scalar
+    + Copy
+    + Num
+    + NumCast
+    + PartialOrd
+    + Neg<Output=Self>
+    + ClosedAdd
+    + ClosedSub
+    + ClosedMul
+    + AddAssign
+    + SubAssign
+    + Sum
+    + Send
+    + Sync
+    + Display
+{}
+
+pub struct QuboProblem<T = QuboType>(CsrMatrix<T>, usize);
+
+impl<T> Problem for QuboProblem<T> {
     type Solution = QuboSolution;
+    type Energy = T;
 }
 
 #[derive(Error, Debug)]
@@ -51,10 +76,15 @@ pub enum QuboError {
     InvalidTriplets(#[from] SparseFormatError),
     #[error("The provided Q Matrix has a non-square size")]
     IncorrectSize,
+    #[error("The coefficients of the Q Matrix overflow their type")]
+    Overflow,
 }
 
-impl QuboProblem {
-    pub fn try_from_q_matrix(q_matrix: CsrMatrix<QuboType>) -> Result<Self, QuboError> {
+impl<T> QuboProblem<T>
+    where
+        T: QuboWeight,
+{
+    pub fn try_from_q_matrix(q_matrix: CsrMatrix<T>) -> Result<Self, QuboError> {
         let n_rows = q_matrix.nrows();
         if n_rows != q_matrix.ncols() {
             Err(QuboError::IncorrectSize)
@@ -63,13 +93,13 @@ impl QuboProblem {
         }
     }
 
-    pub fn try_from_coo_matrix(coo_matrix: &CooMatrix<QuboType>) -> Result<Self, QuboError> {
+    pub fn try_from_coo_matrix(coo_matrix: &CooMatrix<T>) -> Result<Self, QuboError> {
         QuboProblem::try_from_q_matrix(CsrMatrix::from(coo_matrix))
     }
 
     pub fn try_from_triplets(
         problem_size: usize,
-        triplets: Vec<(usize, usize, QuboType)>,
+        triplets: Vec<(usize, usize, T)>,
     ) -> Result<Self, QuboError> {
         let (row_indices, col_indices, values) = {
             let trip_len = triplets.len();
@@ -102,24 +132,26 @@ impl QuboProblem {
 
     pub fn try_from_ising_triplets(
         problem_size: usize,
-        j_triplets: impl IntoIterator<Item=(usize, usize, QuboType)>,
-        j_biases: impl IntoIterator<Item=(usize, QuboType)>,
-    ) -> Result<(Self, QuboType), QuboError> {
+        j_triplets: impl IntoIterator<Item=(usize, usize, T)>,
+        j_biases: impl IntoIterator<Item=(usize, T)>,
+    ) -> Result<(Self, T), QuboError> {
         let mut q_matrix = CooMatrix::new(problem_size, problem_size);
 
-        let mut offset = 0;
+        let mut offset = T::zero();
         for (i, b) in j_biases {
-            q_matrix.push(i, i, 2 * b);
+            let two_b = b + b;
+            q_matrix.push(i, i, two_b);
             offset -= b;
         }
 
         for (i, j, b) in j_triplets {
-            if b == 0 {
+            if b.is_zero() {
                 continue;
             }
-            q_matrix.push(i, j, 4 * b);
-            q_matrix.push(i, i, -2 * b);
-            q_matrix.push(j, j, -2 * b);
+            let two_b = b + b;
+            q_matrix.push(i, j, two_b + two_b);
+            q_matrix.push(i, i, -two_b);
+            q_matrix.push(j, j, -two_b);
 
             offset += b;
         }
@@ -132,13 +164,23 @@ impl QuboProblem {
         self.1
     }
 
-    pub fn evaluate(&self, QuboSolution(solution_vector): &QuboSolution) -> QuboType {
+    /// `E(x) = x^T (Q + Q^T) x = 2 x^T Q x` for the `Q` given to [QuboProblem::try_from_q_matrix], as
+    /// the problem stores `Q + Q^T`. Its diagonal terms are thus counted twice, like the others.
+    pub fn evaluate(&self, solution: &QuboSolution) -> T {
         let QuboProblem(q_matrix, _) = self;
 
-        // Matrix math is associative, and only csr * dense is implemented
-        let xqx = solution_vector.transpose() * (q_matrix * solution_vector);
-        *xqx.get((0, 0))
-            .expect("If dimensions match the final matrix is a 1x1 matrix")
+        // x^T Q x is the sum of every entry whose row and column are both set
+        solution
+            .ones()
+            .map(|i| {
+                let row = q_matrix.row(i);
+
+                zip(row.col_indices(), row.values())
+                    .filter(|(j, _)| solution.get(**j))
+                    .map(|(_, v)| *v)
+                    .sum::<T>()
+            })
+            .sum()
     }
 
     /// Returns the difference between the `E(f(k, f(j, X)))` and `E(f(k, X))`. i.e. the delta obtained
@@ -154,14 +196,14 @@ impl QuboProblem {
     /// * `k`: The bit flipped that created the delta k
     /// * `j`: The bit to flip to produce the solution
     ///
-    /// returns: isize
+    /// returns: T
     pub fn flip_j_and_delta_evaluate_k(
         &self,
         solution: &QuboSolution,
-        delta_k: QuboType,
+        delta_k: T,
         j: usize,
         k: usize,
-    ) -> QuboType {
+    ) -> T {
         if j == k {
             return -delta_k;
         }
@@ -172,10 +214,13 @@ impl QuboProblem {
             .expect("J and K should not be out of bounds!")
             .into_value();
 
-        let s_j = sigma(solution, j);
-        let s_k = sigma(solution, k);
+        let two_w_jk = w_jk + w_jk;
 
-        delta_k + 2 * w_jk * s_j * s_k
+        if sigma(solution, j) == sigma(solution, k) {
+            delta_k + two_w_jk
+        } else {
+            delta_k - two_w_jk
+        }
     }
 
     /// Calculate the delta between a solution and the next solution by flipping bit k
@@ -186,41 +231,46 @@ impl QuboProblem {
     /// * `solution`: The solution
     /// * `k`: The kth bit to flip
     ///
-    /// returns: isize
+    /// returns: T
     pub fn delta_evaluate_k(
         &self,
-        solution @ QuboSolution(solution_vector): &QuboSolution,
+        solution: &QuboSolution,
         k: usize,
-    ) -> QuboType {
+    ) -> T {
         let row = self.0.get_row(k).expect("K should not be out of bounds!");
 
-        let row_sum: QuboType = zip(
+        let row_sum: T = zip(
             row.col_indices().iter().cloned(),
             row.values().iter().cloned(),
         )
-            .map(|(i, x)| solution_vector[i] * x)
+            .filter(|(i, _)| solution.get(*i))
+            .map(|(_, x)| x)
             .sum();
 
-        let sigma_k = sigma(solution, k);
+        let two_row_sum = row_sum + row_sum;
 
         let w_kk = row.get_entry(k).unwrap().into_value();
 
-        -2 * row_sum * sigma_k + w_kk
+        if sigma(solution, k) > 0 {
+            w_kk - two_row_sum
+        } else {
+            w_kk + two_row_sum
+        }
     }
 
-    pub fn get_sparse(&self) -> CsrMatrix<QuboType> {
+    pub fn get_sparse(&self) -> CsrMatrix<T> {
         self.0.clone()
     }
 
-    pub fn get_dense(&self) -> DMatrix<QuboType> {
-        self.0.clone() * DMatrix::identity(self.1, self.1)
+    pub fn get_dense(&self) -> DMatrix<T> {
+        DMatrix::from(&self.0)
     }
 
-    pub fn get_ising(&self) -> (DVector<QuboType>, DMatrix<QuboType>, QuboType) {
+    pub fn get_ising(&self) -> (DVector<T>, DMatrix<T>, T) {
         let problem_size = self.get_size();
-        let mut h_bias_builder: DVector<QuboType> = DVector::zeros(problem_size);
-        let mut j_mat_builder: DMatrix<QuboType> = DMatrix::zeros(problem_size, problem_size);
-        let mut offset = 0;
+        let mut h_bias_builder: DVector<T> = DVector::zeros(problem_size);
+        let mut j_mat_builder: DMatrix<T> = DMatrix::zeros(problem_size, problem_size);
+        let mut offset = T::zero();
 
         for (i, j, &v) in self.get_sparse().upper_triangle().triplet_iter() {
             if i == j {
@@ -239,7 +289,10 @@ impl QuboProblem {
 }
 
 /// Note: This operation is expensive, only print if ABSOLUTELY necessary
-impl Debug for QuboProblem {
+impl<T> Debug for QuboProblem<T>
+    where
+        T: QuboWeight,
+{
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "QuboProblem of size {}{}", self.1, self.get_dense())
     }
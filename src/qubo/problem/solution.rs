@@ -0,0 +1,161 @@
+use std::fmt::{Display, Formatter};
+use std::iter::zip;
+
+use nalgebra::DVector;
+
+use crate::qubo::QuboType;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A binary solution of a [QuboProblem](crate::qubo::QuboProblem), packed 64 bits to a word.
+///
+/// The bits past the length are always zero, so that equal solutions have equal words and hash
+/// alike.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct QuboSolution {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl QuboSolution {
+    pub fn zeros(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(WORD_BITS)],
+            len,
+        }
+    }
+
+    pub fn from_fn(len: usize, mut f: impl FnMut(usize) -> bool) -> Self {
+        let mut solution = Self::zeros(len);
+        for i in 0..len {
+            if f(i) {
+                solution.words[i / WORD_BITS] |= 1 << (i % WORD_BITS);
+            }
+        }
+
+        solution
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "Bit {i} is out of bounds for a solution of length {}", self.len);
+
+        self.words[i / WORD_BITS] >> (i % WORD_BITS) & 1 == 1
+    }
+
+    /// The bit `i` as a `0` or a `1`
+    pub fn value(&self, i: usize) -> QuboType {
+        self.get(i) as QuboType
+    }
+
+    pub fn set(&mut self, i: usize, bit: bool) {
+        if self.get(i) != bit {
+            self.flip_in_place(i);
+        }
+    }
+
+    /// Returns a copy of the solution with bit `i` flipped
+    pub fn flip(&self, i: usize) -> Self {
+        let mut temp_solution = self.clone();
+
+        temp_solution.flip_in_place(i);
+
+        temp_solution
+    }
+
+    pub fn flip_in_place(&mut self, i: usize) {
+        assert!(i < self.len, "Bit {i} is out of bounds for a solution of length {}", self.len);
+
+        self.words[i / WORD_BITS] ^= 1 << (i % WORD_BITS);
+    }
+
+    /// The number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// The number of bits that differ between two solutions of the same length
+    pub fn hamming_distance(&self, other: &Self) -> usize {
+        assert_eq!(self.len, other.len, "Solutions must have the same length");
+
+        zip(&self.words, &other.words)
+            .map(|(a, b)| (a ^ b).count_ones() as usize)
+            .sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=bool> + '_ {
+        (0..self.len).map(|i| self.get(i))
+    }
+
+    /// The indices of the set bits, in increasing order
+    pub fn ones(&self) -> impl Iterator<Item=usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(w, &word)| {
+            let mut remaining = word;
+
+            std::iter::from_fn(move || {
+                if remaining == 0 {
+                    return None;
+                }
+
+                let bit = remaining.trailing_zeros() as usize;
+                remaining &= remaining - 1;
+
+                Some(w * WORD_BITS + bit)
+            })
+        })
+    }
+
+    pub fn to_vector(&self) -> DVector<QuboType> {
+        DVector::from_fn(self.len, |i, _| self.value(i))
+    }
+}
+
+/// Any non-zero entry is a set bit
+impl From<&DVector<QuboType>> for QuboSolution {
+    fn from(solution_vector: &DVector<QuboType>) -> Self {
+        Self::from_fn(solution_vector.len(), |i| solution_vector[i] != 0)
+    }
+}
+
+impl From<DVector<QuboType>> for QuboSolution {
+    fn from(solution_vector: DVector<QuboType>) -> Self {
+        Self::from(&solution_vector)
+    }
+}
+
+impl From<&QuboSolution> for DVector<QuboType> {
+    fn from(solution: &QuboSolution) -> Self {
+        solution.to_vector()
+    }
+}
+
+impl From<QuboSolution> for DVector<QuboType> {
+    fn from(solution: QuboSolution) -> Self {
+        solution.to_vector()
+    }
+}
+
+impl FromIterator<bool> for QuboSolution {
+    fn from_iter<I: IntoIterator<Item=bool>>(iter: I) -> Self {
+        let bits: Vec<bool> = iter.into_iter().collect();
+
+        Self::from_fn(bits.len(), |i| bits[i])
+    }
+}
+
+impl Display for QuboSolution {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for i in 0..self.len {
+            write!(f, "{:1}", self.value(i))?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,8 +1,11 @@
+use std::collections::HashSet;
+use std::iter::zip;
+
 use nalgebra::{DMatrix, dvector, DVector};
 use nalgebra_sparse::{CooMatrix, CsrMatrix};
 use rand::{Rng, thread_rng};
 
-use super::{QuboProblem, QuboSolution, QuboType};
+use super::{LocalFieldState, QuboError, QuboFileError, QuboProblem, QuboSolution, QuboType};
 
 #[test]
 fn check_evaluation() {
@@ -20,9 +23,10 @@ fn check_evaluation() {
     };
 
     let sut = QuboProblem::try_from_q_matrix(sut_internal).expect("Matrix is supposedly valid");
-    let sut_solution = QuboSolution(dvector![1, 1, 1]);
+    let sut_solution = QuboSolution::from(dvector![1, 1, 1]);
 
-    assert_eq!(9, sut.evaluate(&sut_solution));
+    // The problem stores Q + Q^T, so every term of Q is counted twice
+    assert_eq!(12, sut.evaluate(&sut_solution));
 }
 
 #[test]
@@ -41,7 +45,7 @@ fn check_delta_evaluation_jk() {
         .upper_triangle();
 
     let sut = QuboProblem::try_from_q_matrix(sut_internal).expect("Matrix is supposedly valid");
-    let sut_solution = QuboSolution(DVector::from_iterator(
+    let sut_solution = QuboSolution::from(DVector::from_iterator(
         PROBLEM_SIZE,
         (0..PROBLEM_SIZE).map(|_| rng.gen_range(0..=1)),
     ));
@@ -87,7 +91,7 @@ fn check_delta_evaluation_k() {
 
     println!("Generated problem: {:?}", sut);
 
-    let sut_solution = QuboSolution(DVector::from_fn(PROBLEM_SIZE, |_, _| rng.gen_range(0..=1)));
+    let sut_solution = QuboSolution::from(DVector::from_fn(PROBLEM_SIZE, |_, _| rng.gen_range(0..=1)));
 
     let eval = sut.evaluate(&sut_solution);
 
@@ -106,6 +110,106 @@ fn check_delta_evaluation_k() {
     }
 }
 
+#[test]
+fn check_local_field_state() {
+    const PROBLEM_SIZE: usize = 50;
+
+    let mut rng = thread_rng();
+
+    // Sparse, so that most flips leave most deltas alone
+    let triplets = (0..4 * PROBLEM_SIZE)
+        .map(|_| (rng.gen_range(0..PROBLEM_SIZE), rng.gen_range(0..PROBLEM_SIZE), rng.gen_range(-64..64)))
+        .collect();
+    let sut_problem = QuboProblem::try_from_triplets(PROBLEM_SIZE, triplets).expect("Triplets are supposedly valid");
+
+    let start = QuboSolution::from(DVector::from_fn(PROBLEM_SIZE, |_, _| rng.gen_range(0..=1)));
+    let mut sut = LocalFieldState::new(&sut_problem, start);
+
+    for _ in 0..200 {
+        let i = rng.gen_range(0..PROBLEM_SIZE);
+        let expected_evaluation = sut.evaluation() + sut.delta(i);
+
+        sut.flip(&sut_problem, i);
+
+        assert_eq!(expected_evaluation, sut.evaluation());
+        assert_eq!(sut_problem.evaluate(sut.solution()), sut.evaluation());
+        for k in 0..PROBLEM_SIZE {
+            assert_eq!(sut_problem.delta_evaluate_k(sut.solution(), k), sut.delta(k));
+        }
+    }
+
+    // Flips below a limit keep the deltas below it exact, and undoing them restores every delta
+    let before = sut.clone();
+    let limit = PROBLEM_SIZE / 2;
+    let flipped: Vec<_> = (0..20).map(|_| rng.gen_range(0..limit)).collect();
+    for &i in &flipped {
+        sut.flip_below(&sut_problem, i, limit);
+
+        assert_eq!(sut_problem.evaluate(sut.solution()), sut.evaluation());
+        for k in 0..limit {
+            assert_eq!(sut_problem.delta_evaluate_k(sut.solution(), k), sut.delta(k));
+        }
+    }
+    for &i in flipped.iter().rev() {
+        sut.flip_below(&sut_problem, i, limit);
+    }
+
+    assert_eq!(before.solution(), sut.solution());
+    assert_eq!(before.deltas(), sut.deltas());
+
+    // Descending over some bits leaves the others alone, and none of its bits can be improved
+    sut.descend(&sut_problem, limit..PROBLEM_SIZE);
+
+    assert_eq!(sut_problem.evaluate(sut.solution()), sut.evaluation());
+    assert!(sut.evaluation() <= before.evaluation());
+    assert!((0..limit).all(|k| sut.solution().get(k) == before.solution().get(k)));
+    assert!((limit..PROBLEM_SIZE).all(|k| sut.delta(k) >= 0));
+}
+
+#[test]
+fn check_packed_solution() {
+    // Spans several words, with a partial last one
+    const PROBLEM_SIZE: usize = 150;
+
+    let mut rng = thread_rng();
+
+    let vector: DVector<QuboType> = DVector::from_fn(PROBLEM_SIZE, |_, _| rng.gen_range(0..=1));
+    let mut sut = QuboSolution::from(&vector);
+
+    assert_eq!(PROBLEM_SIZE, sut.len());
+    assert_eq!(vector, sut.to_vector());
+    assert_eq!(vector.sum() as usize, sut.count_ones());
+    assert_eq!(
+        (0..PROBLEM_SIZE).filter(|i| vector[*i] == 1).collect::<Vec<_>>(),
+        sut.ones().collect::<Vec<_>>()
+    );
+    assert_eq!(vector.iter().map(|x| *x == 1).collect::<QuboSolution>(), sut);
+
+    let original = sut.clone();
+    let flipped: HashSet<usize> = (0..40).map(|_| rng.gen_range(0..PROBLEM_SIZE)).collect();
+    for &i in &flipped {
+        let bit = sut.get(i);
+
+        sut.flip_in_place(i);
+
+        assert_eq!(!bit, sut.get(i));
+        assert_eq!(sut.get(i), original.flip(i).get(i));
+    }
+    assert_eq!(flipped.len(), sut.hamming_distance(&original));
+
+    for &i in &flipped {
+        sut.set(i, original.get(i));
+    }
+
+    // Equal solutions hash alike, whatever sequence of flips produced them
+    assert_eq!(original, sut);
+    assert_eq!(HashSet::from([original.clone()]), HashSet::from([sut.clone()]));
+    assert_eq!(original.to_string(), vector.iter().map(|x| x.to_string()).collect::<String>());
+
+    assert_eq!(0, QuboSolution::zeros(PROBLEM_SIZE).count_ones());
+    assert!(QuboSolution::zeros(0).is_empty());
+}
+
 #[test]
 fn check_ising() {
     const PROBLEM_SIZE: usize = 100;
@@ -130,3 +234,151 @@ fn check_ising() {
     j_t.set_diagonal(&h_t);
     assert_eq!(sut_internal * DMatrix::identity(PROBLEM_SIZE, PROBLEM_SIZE), j_t / 4);
 }
+
+#[test]
+fn check_generic_weights() {
+    const PROBLEM_SIZE: usize = 50;
+
+    let mut rng = thread_rng();
+
+    let weights = DMatrix::from_fn(PROBLEM_SIZE, PROBLEM_SIZE, |i, j| {
+        if i <= j {
+            rng.gen_range(-128..128)
+        } else {
+            0
+        }
+    });
+
+    let sut_i32 = QuboProblem::try_from_q_matrix(CsrMatrix::from(&weights)).unwrap();
+    let sut_i64 = QuboProblem::try_from_q_matrix(CsrMatrix::from(&weights.map(|x| x as i64))).unwrap();
+    let sut_f64 = QuboProblem::try_from_q_matrix(CsrMatrix::from(&weights.map(|x| x as f64 / 4.0))).unwrap();
+
+    let sut_solution = QuboSolution::from(DVector::from_fn(PROBLEM_SIZE, |_, _| rng.gen_range(0..=1)));
+
+    let eval = sut_i32.evaluate(&sut_solution);
+    assert_eq!(eval as i64, sut_i64.evaluate(&sut_solution));
+    assert_eq!(eval as f64 / 4.0, sut_f64.evaluate(&sut_solution));
+
+    for k in 0..PROBLEM_SIZE {
+        let delta_k = sut_i32.delta_evaluate_k(&sut_solution, k);
+
+        assert_eq!(delta_k as i64, sut_i64.delta_evaluate_k(&sut_solution, k));
+        assert_eq!(delta_k as f64 / 4.0, sut_f64.delta_evaluate_k(&sut_solution, k));
+    }
+}
+
+#[test]
+fn check_file_formats() {
+    // Q = [[1, -3, 0], [0, 2, 5], [0, 0, -4]] has E(1, 1, 0) = 1 - 3 + 2 = 0 and E(1, 0, 1) = -3
+    let qbsolv = "c comment\np qubo 0 3 3 2\n0 0 1\n1 1 2\n2 2 -4\n0 1 -3\n1 2 5\n";
+    let matrix_market = "%%MatrixMarket matrix coordinate integer general\n% comment\n3 3 5\n1 1 1\n2 2 2\n3 3 -4\n1 2 -3\n2 3 5\n";
+    let symmetric = "%%MatrixMarket matrix coordinate real symmetric\n3 3 5\n1 1 1\n2 2 2\n3 3 -4\n2 1 -1.5\n3 2 2.5\n";
+    // ORLib problems are maximised, so every coefficient is negated
+    let bqp = "1\n3 5\n1 1 -1\n2 2 -2\n3 3 4\n1 2 1.5 2 3\n-2.5\n";
+
+    let solutions = [dvector![1, 1, 0], dvector![1, 0, 1], dvector![0, 1, 1], dvector![1, 1, 1]];
+    let energies = [0.0, -3.0, 3.0, 1.0];
+
+    let problems: Vec<QuboProblem<f64>> = vec![
+        QuboProblem::from_qbsolv_file(qbsolv.as_bytes()).unwrap(),
+        QuboProblem::from_matrix_market_file(matrix_market.as_bytes()).unwrap(),
+        QuboProblem::from_matrix_market_file(symmetric.as_bytes()).unwrap(),
+        QuboProblem::from_bqp_file(bqp.as_bytes()).unwrap().remove(0),
+    ];
+
+    for problem in problems {
+        for (solution, energy) in zip(&solutions, energies) {
+            assert_eq!(problem.evaluate(&QuboSolution::from(solution.clone())), 2.0 * energy);
+        }
+    }
+
+    let problem: QuboProblem = QuboProblem::from_qbsolv_file(qbsolv.as_bytes()).unwrap();
+    let mut qbsolv_out = Vec::new();
+    let mut matrix_market_out = Vec::new();
+    problem.write_qbsolv(&mut qbsolv_out).unwrap();
+    problem.write_matrix_market(&mut matrix_market_out).unwrap();
+
+    let round_trips: Vec<QuboProblem> = vec![
+        QuboProblem::from_qbsolv_file(qbsolv_out.as_slice()).unwrap(),
+        QuboProblem::from_matrix_market_file(matrix_market_out.as_slice()).unwrap(),
+    ];
+    for round_trip in round_trips {
+        assert_eq!(round_trip.get_dense(), problem.get_dense());
+    }
+
+    // Odd couplers cannot be split into a symmetric matrix of integers
+    assert!(matches!(problem.write_bqp(Vec::new()), Err(QuboFileError::Unrepresentable(_))));
+
+    let even = QuboProblem::try_from_triplets(2, vec![(0, 0, 3), (0, 1, -4)]).unwrap();
+    let mut bqp_out = Vec::new();
+    even.write_bqp(&mut bqp_out).unwrap();
+    assert_eq!(QuboProblem::from_bqp_file(bqp_out.as_slice()).unwrap()[0].get_dense(), even.get_dense());
+
+    // The declared size is kept even when the last variables are never used
+    let trailing = QuboProblem::try_from_triplets(3, vec![(0, 0, 1)]).unwrap();
+    let files = [
+        QuboProblem::from_qbsolv_file("p qubo 0 3 1 0\n0 0 1\n".as_bytes()).unwrap(),
+        QuboProblem::from_matrix_market_file(
+            "%%MatrixMarket matrix coordinate integer symmetric\n3 3 1\n1 1 1\n".as_bytes()
+        ).unwrap(),
+        QuboProblem::from_bqp_file("1\n3 1\n1 1 -1\n".as_bytes()).unwrap().remove(0),
+    ];
+    for file in files {
+        assert_eq!(file.get_size(), 3);
+        assert_eq!(file.get_dense(), trailing.get_dense());
+    }
+
+    let mut qbsolv_out = Vec::new();
+    let mut bqp_out = Vec::new();
+    trailing.write_qbsolv(&mut qbsolv_out).unwrap();
+    trailing.write_bqp(&mut bqp_out).unwrap();
+    assert_eq!(QuboProblem::from_qbsolv_file(qbsolv_out.as_slice()).unwrap().get_dense(), trailing.get_dense());
+    assert_eq!(QuboProblem::from_bqp_file(bqp_out.as_slice()).unwrap()[0].get_dense(), trailing.get_dense());
+}
+
+#[test]
+fn check_file_errors() {
+    let qbsolv = |file: &str| QuboProblem::<i32>::from_qbsolv_file(file.as_bytes());
+
+    assert!(matches!(qbsolv(""), Err(QuboFileError::MissingHeader)));
+    assert!(matches!(qbsolv("p qubo 0 3 1\n"), Err(QuboFileError::InvalidHeader { line: 1 })));
+    assert!(matches!(qbsolv("p qubo 0 3 1 0\n\n0 0 x\n"), Err(QuboFileError::InvalidEntry { line: 3 })));
+    assert!(matches!(qbsolv("p qubo 0 3 1 0\n0 3 1\n"), Err(QuboFileError::InvalidIndex { line: 2 })));
+    // Hostile headers are rejected before anything is allocated from them
+    assert!(matches!(
+        qbsolv("p qubo 0 3 18446744073709551615 1\n"),
+        Err(QuboFileError::InvalidHeader { line: 1 })
+    ));
+    assert!(matches!(
+        qbsolv("p qubo 0 18446744073709551615 1 0\n0 0 1\n"),
+        Err(QuboFileError::InvalidHeader { line: 1 })
+    ));
+    assert!(matches!(qbsolv("p qubo 0 1000000000000 0 0\n"), Err(QuboFileError::InvalidHeader { line: 1 })));
+
+    assert!(matches!(
+        QuboProblem::<i32>::from_matrix_market_file("%%MatrixMarket matrix array real general\n".as_bytes()),
+        Err(QuboFileError::InvalidHeader { line: 1 })
+    ));
+    assert!(matches!(
+        QuboProblem::<i32>::from_matrix_market_file("%%MatrixMarket matrix coordinate real general\n2 3 0\n".as_bytes()),
+        Err(QuboFileError::InvalidProblem(QuboError::IncorrectSize))
+    ));
+    assert!(matches!(
+        QuboProblem::<i32>::from_matrix_market_file(
+            "%%MatrixMarket matrix coordinate real general\n% comment\n1000000000000 1000000000000 1\n1 1 1\n".as_bytes()
+        ),
+        Err(QuboFileError::InvalidHeader { line: 3 })
+    ));
+    assert!(matches!(
+        QuboProblem::<i32>::from_bqp_file("2\n3 1\n1 1 1\n".as_bytes()),
+        Err(QuboFileError::UnexpectedEnd)
+    ));
+    assert!(matches!(
+        QuboProblem::<i32>::from_bqp_file("18446744073709551615\n1 18446744073709551615\n".as_bytes()),
+        Err(QuboFileError::UnexpectedEnd)
+    ));
+    assert!(matches!(
+        QuboProblem::<i32>::from_bqp_file("1\n1000000000000 1\n1 1 1\n".as_bytes()),
+        Err(QuboFileError::InvalidHeader { line: 2 })
+    ));
+}
@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+
+/// A flow network solved with Dinic's algorithm. Residual capacities at or below `epsilon` count as
+/// saturated, so that rounding errors in real capacities do not leave phantom paths behind.
+pub(crate) struct FlowNetwork {
+    // Arcs are stored in pairs, so the reverse of arc `a` is `a ^ 1`
+    heads: Vec<usize>,
+    residuals: Vec<f64>,
+    adjacency: Vec<Vec<usize>>,
+    epsilon: f64,
+}
+
+impl FlowNetwork {
+    pub fn new(nb_nodes: usize) -> Self {
+        Self {
+            heads: Vec::new(),
+            residuals: Vec::new(),
+            adjacency: vec![Vec::new(); nb_nodes],
+            epsilon: 0.0,
+        }
+    }
+
+    pub fn add_arc(&mut self, from: usize, to: usize, capacity: f64) {
+        if capacity <= 0.0 {
+            return;
+        }
+
+        self.adjacency[from].push(self.heads.len());
+        self.heads.push(to);
+        self.residuals.push(capacity);
+
+        self.adjacency[to].push(self.heads.len());
+        self.heads.push(from);
+        self.residuals.push(0.0);
+
+        self.epsilon = f64::max(self.epsilon, capacity * 1e-12);
+    }
+
+    /// The distance of every node from `source` through unsaturated arcs, if it can be reached
+    fn levels(&self, source: usize) -> Vec<Option<usize>> {
+        let mut levels = vec![None; self.adjacency.len()];
+        let mut queue = VecDeque::from([source]);
+        levels[source] = Some(0);
+
+        while let Some(node) = queue.pop_front() {
+            for &arc in &self.adjacency[node] {
+                let head = self.heads[arc];
+
+                if levels[head].is_none() && self.residuals[arc] > self.epsilon {
+                    levels[head] = levels[node].map(|level| level + 1);
+                    queue.push_back(head);
+                }
+            }
+        }
+
+        levels
+    }
+
+    /// Pushes a blocking flow along the shortest paths from `source` to `sink`, returning its value.
+    /// The paths are followed iteratively, as they can be as long as the network is large.
+    fn blocking_flow(&mut self, source: usize, sink: usize, levels: &[Option<usize>]) -> f64 {
+        let mut next_arc = vec![0; self.adjacency.len()];
+        let mut is_dead_end = vec![false; self.adjacency.len()];
+        let mut path: Vec<usize> = Vec::new();
+        let mut node = source;
+        let mut flow = 0.0;
+
+        loop {
+            if node == sink {
+                let pushed = path.iter().map(|&arc| self.residuals[arc]).fold(f64::INFINITY, f64::min);
+
+                for &arc in &path {
+                    self.residuals[arc] -= pushed;
+                    self.residuals[arc ^ 1] += pushed;
+                }
+                flow += pushed;
+
+                // Resume from the tail of the first arc that was saturated
+                let saturated = path
+                    .iter()
+                    .position(|&arc| self.residuals[arc] <= self.epsilon)
+                    .expect("The bottleneck is saturated");
+                node = self.heads[path[saturated] ^ 1];
+                path.truncate(saturated);
+
+                continue;
+            }
+
+            let next = self.adjacency[node][next_arc[node]..].iter().position(|&arc| {
+                let head = self.heads[arc];
+
+                self.residuals[arc] > self.epsilon
+                    && !is_dead_end[head]
+                    && levels[head] == levels[node].map(|level| level + 1)
+            });
+
+            match next {
+                Some(offset) => {
+                    next_arc[node] += offset;
+
+                    let arc = self.adjacency[node][next_arc[node]];
+                    path.push(arc);
+                    node = self.heads[arc];
+                }
+                None if node == source => return flow,
+                None => {
+                    is_dead_end[node] = true;
+
+                    let arc = path.pop().expect("Only the source has an empty path");
+                    node = self.heads[arc ^ 1];
+                    next_arc[node] += 1;
+                }
+            }
+        }
+    }
+
+    /// Saturates the network and returns the value of the maximum flow
+    pub fn max_flow(&mut self, source: usize, sink: usize) -> f64 {
+        let mut flow = 0.0;
+
+        loop {
+            let levels = self.levels(source);
+            if levels[sink].is_none() {
+                return flow;
+            }
+
+            flow += self.blocking_flow(source, sink, &levels);
+        }
+    }
+
+    /// The nodes that can still be reached from `source` through unsaturated arcs
+    pub fn reachable(&self, source: usize) -> Vec<bool> {
+        self.levels(source).iter().map(Option::is_some).collect()
+    }
+}
@@ -0,0 +1,5 @@
+mod max_flow;
+pub mod roof_duality;
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,158 @@
+use log::debug;
+
+use crate::core::{DownModel, Reduction};
+use crate::qubo::{QuboProblem, QuboSolution, QuboType, QuboWeight};
+use crate::qubo::reductions::max_flow::FlowNetwork;
+use crate::utils::to_f64;
+
+/// Fixes the variables whose optimal values are known from the roof dual of the problem, leaving a
+/// smaller problem over the remaining variables (Boros and Hammer, 2002).
+///
+/// The roof dual is computed as a minimum cut of a network with a node for each variable and one for
+/// its complement, as in QPBO (Kolmogorov and Rother, 2007). The values it assigns are weakly
+/// persistent: some optimal solution of the full problem agrees with all of them, so an optimal
+/// solution of the smaller problem up-models to an optimal solution of the full one.
+///
+/// For every solution `x` of the smaller problem, `E(up_model(x)) = E'(x) + offset()`.
+pub struct RoofDuality<T = QuboType> {
+    fixed: Vec<Option<bool>>,
+    free: Vec<usize>,
+    offset: T,
+}
+
+impl<T> RoofDuality<T>
+    where
+        T: QuboWeight,
+{
+    /// The energy of the fixed variables, which the smaller problem leaves out
+    pub fn offset(&self) -> T {
+        self.offset
+    }
+
+    /// The value of every variable that was fixed, indexed by the variables of the full problem
+    pub fn fixed(&self) -> &[Option<bool>] {
+        &self.fixed
+    }
+
+    pub fn nb_fixed(&self) -> usize {
+        self.fixed.len() - self.free.len()
+    }
+}
+
+/// Labels the variables from a minimum cut of the implication network. Variable `p` has a node `p`
+/// and a node `n + p` for its complement, and a node is on the source side when its literal is `0`.
+fn roof_dual<T>(qubo_problem: &QuboProblem<T>) -> Vec<Option<bool>>
+    where
+        T: QuboWeight,
+{
+    let problem_size = qubo_problem.get_size();
+    let complement = |p: usize| problem_size + p;
+    let (source, sink) = (2 * problem_size, 2 * problem_size + 1);
+
+    let mut network = FlowNetwork::new(2 * problem_size + 2);
+    let mut linear = vec![0.0; problem_size];
+
+    // E(x) = sum_p S_pp x_p + sum_{p<q} 2 S_pq x_p x_q, with every term written with a non negative
+    // coefficient on a product of literals. Each term gives a pair of symmetric arcs.
+    for (p, q, v) in qubo_problem.get_sparse().triplet_iter() {
+        let v = to_f64(*v);
+
+        if p == q {
+            linear[p] += v;
+        } else if p < q && v < 0.0 {
+            // 2v x_p x_q = 2v x_p + |2v| x_p (1 - x_q)
+            linear[p] += 2.0 * v;
+            network.add_arc(q, p, -2.0 * v);
+            network.add_arc(complement(p), complement(q), -2.0 * v);
+        } else if p < q && v > 0.0 {
+            network.add_arc(complement(p), q, 2.0 * v);
+            network.add_arc(complement(q), p, 2.0 * v);
+        }
+    }
+
+    for (p, a) in linear.into_iter().enumerate() {
+        if a > 0.0 {
+            network.add_arc(source, p, a);
+            network.add_arc(complement(p), sink, a);
+        } else if a < 0.0 {
+            // a x_p = a + |a| (1 - x_p), and constants do not change the cut
+            network.add_arc(p, sink, -a);
+            network.add_arc(source, complement(p), -a);
+        }
+    }
+
+    network.max_flow(source, sink);
+    let is_source_side = network.reachable(source);
+
+    (0..problem_size)
+        .map(|p| match (is_source_side[p], is_source_side[complement(p)]) {
+            (true, false) => Some(false),
+            (false, true) => Some(true),
+            _ => None,
+        })
+        .collect()
+}
+
+impl<T> Reduction<QuboProblem<T>, QuboProblem<T>> for RoofDuality<T>
+    where
+        T: QuboWeight,
+{
+    fn reduce(qubo_problem: &QuboProblem<T>) -> (QuboProblem<T>, Self) {
+        let fixed = roof_dual(qubo_problem);
+        let free: Vec<usize> = (0..fixed.len()).filter(|i| fixed[*i].is_none()).collect();
+
+        let mut new_index = vec![None; fixed.len()];
+        for (i, &p) in free.iter().enumerate() {
+            new_index[p] = Some(i);
+        }
+
+        let two = T::one() + T::one();
+        let mut diagonal = vec![T::zero(); free.len()];
+        let mut triplets = Vec::new();
+        let mut offset = T::zero();
+
+        for (p, q, &v) in qubo_problem.get_sparse().triplet_iter() {
+            match (new_index[p], new_index[q], fixed[p], fixed[q]) {
+                (Some(i), Some(_), _, _) if p == q => diagonal[i] += v,
+                (Some(i), Some(j), _, _) if i < j => triplets.push((i, j, v)),
+                // Every free variable is coupled twice to a fixed one, once from each side
+                (Some(i), None, _, Some(true)) | (None, Some(i), Some(true), _) => diagonal[i] += v,
+                (None, None, Some(true), Some(true)) => offset += v,
+                _ => {}
+            }
+        }
+
+        // The problem is built from Q, with S = Q + Q^T, so the diagonal of S is twice that of Q
+        triplets.extend(diagonal.into_iter().enumerate().map(|(i, v)| (i, i, v / two)));
+
+        debug!("Roof duality fixed {} of {} variables", fixed.len() - free.len(), fixed.len());
+
+        (
+            QuboProblem::try_from_triplets(free.len(), triplets).expect("Indices are within the smaller problem"),
+            Self { fixed, free, offset },
+        )
+    }
+
+    fn up_model(&self, solution: QuboSolution) -> QuboSolution {
+        let mut full_solution = QuboSolution::from_fn(self.fixed.len(), |i| self.fixed[i].unwrap_or(false));
+
+        for (i, &p) in self.free.iter().enumerate() {
+            full_solution.set(p, solution.get(i));
+        }
+
+        full_solution
+    }
+}
+
+impl<T> DownModel<QuboProblem<T>, QuboProblem<T>> for RoofDuality<T>
+    where
+        T: QuboWeight,
+{
+    /// Keeps the free variables of the solution. Its values of the fixed variables are dropped, so a
+    /// solution that disagrees with them comes back up with a different energy.
+    fn down_model(&self, _: &QuboProblem<T>, solution: QuboSolution) -> QuboSolution {
+        assert_eq!(self.fixed.len(), solution.len(), "The solution must have the size of the full problem");
+
+        QuboSolution::from_fn(self.free.len(), |i| solution.get(self.free[i]))
+    }
+}
@@ -0,0 +1,82 @@
+use nalgebra::{DMatrix, DVector};
+use nalgebra_sparse::CsrMatrix;
+use rand::prelude::*;
+
+use crate::core::{Reduction, Solver};
+use crate::qubo::{QuboProblem, QuboSolution};
+use crate::qubo::reductions::max_flow::FlowNetwork;
+use crate::qubo::reductions::roof_duality::RoofDuality;
+use crate::qubo::solvers::ExhaustiveSearch;
+
+fn random_problem(rng: &mut impl Rng, problem_size: usize, density: f64) -> QuboProblem {
+    let q_matrix = CsrMatrix::from(&DMatrix::from_fn(problem_size, problem_size, |i, j| {
+        if i <= j && rng.gen_bool(density) { rng.gen_range(-16..16) } else { 0 }
+    }));
+
+    QuboProblem::try_from_q_matrix(q_matrix).unwrap()
+}
+
+#[test]
+fn check_max_flow() {
+    // Two paths from 0 to 3, with a cross arc that lets the flow avoid the bottleneck 1 -> 3
+    let mut network = FlowNetwork::new(4);
+    network.add_arc(0, 1, 3.0);
+    network.add_arc(0, 2, 2.0);
+    network.add_arc(1, 2, 2.0);
+    network.add_arc(1, 3, 1.0);
+    network.add_arc(2, 3, 4.0);
+
+    assert_eq!(network.max_flow(0, 3), 5.0);
+    assert_eq!(network.reachable(0), vec![true, false, false, false]);
+}
+
+#[test]
+fn check_roof_duality() {
+    let mut rng = StdRng::seed_from_u64(16);
+    let mut nb_fixed = 0;
+
+    for _ in 0..50 {
+        let problem = random_problem(&mut rng, 12, 0.3);
+        let (reduced_problem, reducer) = RoofDuality::reduce(&problem);
+        nb_fixed += reducer.nb_fixed();
+
+        assert_eq!(reduced_problem.get_size() + reducer.nb_fixed(), problem.get_size());
+
+        // The energy of every solution of the smaller problem is kept
+        for _ in 0..20 {
+            let solution = QuboSolution::from(DVector::from_fn(reduced_problem.get_size(), |_, _| rng.gen_range(0..=1)));
+
+            assert_eq!(
+                problem.evaluate(&reducer.up_model(solution.clone())),
+                reduced_problem.evaluate(&solution) + reducer.offset()
+            );
+        }
+
+        // The fixed values are persistent, so the optimum is kept as well
+        let optimum = problem.evaluate(&ExhaustiveSearch::new().solve(&problem));
+        let reduced_optimum = problem.evaluate(&reducer.up_model(ExhaustiveSearch::new().solve(&reduced_problem)));
+
+        assert_eq!(optimum, reduced_optimum);
+    }
+
+    assert!(nb_fixed > 0);
+}
+
+#[test]
+fn check_roof_duality_submodular() {
+    let mut rng = StdRng::seed_from_u64(17);
+
+    // The roof dual is tight for problems without positive couplings, so every variable is fixed
+    let q_matrix = CsrMatrix::from(&DMatrix::from_fn(20, 20, |i, j| {
+        if i == j { rng.gen_range(-16..16) } else if i < j { rng.gen_range(-16..=0) } else { 0 }
+    }));
+    let problem = QuboProblem::try_from_q_matrix(q_matrix).unwrap();
+
+    let (reduced_problem, reducer) = RoofDuality::reduce(&problem);
+
+    assert_eq!(reduced_problem.get_size(), 0);
+    assert_eq!(
+        problem.evaluate(&reducer.up_model(QuboSolution::from(DVector::zeros(0)))),
+        problem.evaluate(&ExhaustiveSearch::new().solve(&problem))
+    );
+}
@@ -0,0 +1,369 @@
+use std::cmp::Ordering;
+use std::sync::{Arc, Mutex};
+use std::thread::available_parallelism;
+
+use log::debug;
+use nalgebra::DVector;
+use rayon::prelude::*;
+
+use crate::core::{SampleSet, Solver, StoppingCondition, StoppingCriteria};
+use crate::logging::{Observers, SolverEvent, SolverObserver};
+use crate::qubo::{QuboProblem, QuboSolution, QuboType, QuboWeight};
+use crate::qubo::solvers::TabuSearch;
+
+// Nodes explored between two checks of the stopping criteria and of the shared incumbent
+const CHECK_INTERVAL: usize = 1 << 10;
+
+/// The order in which [BranchAndBound] fixes the variables
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VariableOrder {
+    /// By index
+    Index,
+    /// By decreasing total magnitude of the coefficients of each variable, computed once
+    Weight,
+    /// The free variable with the largest magnitude of its linear coefficient and of its couplings to
+    /// the other free variables, chosen again at every node
+    Dynamic,
+}
+
+/// An exact branch-and-bound search. Variables are fixed one at a time, and a sub tree is pruned
+/// as soon as a lower bound on its energy is no better than the best solution found so far, which
+/// is seeded by a short tabu search.
+///
+/// With the free variables `U`, the energy of a node is `c + sum_{i in U} x_i (l_i + sum_{j in U}
+/// S_ij x_j)` where `c` and `l_i` absorb the fixed variables, so it is bounded below by `c + sum_{i in
+/// U} min(0, l_i + sum_{j in U} min(0, S_ij))`.
+///
+/// The first `split_depth` variables are enumerated up front, and the resulting sub trees are
+/// searched in parallel while sharing the incumbent.
+pub struct BranchAndBound<T = QuboType>
+{
+    order: VariableOrder,
+    split_depth: usize,
+    seed: Option<u64>,
+    observers: Observers<T>,
+}
+
+impl<T> Default for BranchAndBound<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> BranchAndBound<T>
+{
+    pub fn new() -> Self {
+        // A few sub trees per thread balance the work, as their sizes vary widely
+        let nb_threads = available_parallelism().map_or(1, |n| n.get());
+
+        Self {
+            order: VariableOrder::Dynamic,
+            split_depth: nb_threads.next_power_of_two().ilog2() as usize + 2,
+            seed: None,
+            observers: Observers::default(),
+        }
+    }
+
+    /// Defaults to [VariableOrder::Dynamic]
+    pub fn with_order(mut self, order: VariableOrder) -> Self {
+        self.order = order;
+
+        self
+    }
+
+    /// The number of variables fixed before the sub trees are searched in parallel, at most
+    /// `usize::BITS - 1`
+    pub fn with_split_depth(mut self, split_depth: usize) -> Self {
+        self.split_depth = split_depth;
+
+        self
+    }
+
+    /// Seeds the tabu search that provides the first incumbent
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+
+        self
+    }
+
+    pub fn with_observer(mut self, observer: Arc<dyn SolverObserver<T>>) -> Self {
+        self.observers.push(observer);
+
+        self
+    }
+}
+
+fn negative_part<T: QuboWeight>(x: T) -> T {
+    if x < T::zero() { x } else { T::zero() }
+}
+
+fn magnitude<T: QuboWeight>(x: T) -> T {
+    if x < T::zero() { -x } else { x }
+}
+
+/// The off diagonal entries of each row of `S`
+fn couplings<T>(qubo_problem: &QuboProblem<T>) -> Vec<Vec<(usize, T)>>
+    where
+        T: QuboWeight,
+{
+    qubo_problem
+        .get_sparse()
+        .row_iter()
+        .enumerate()
+        .map(|(i, row)| {
+            row.col_indices()
+                .iter()
+                .cloned()
+                .zip(row.values().iter().cloned())
+                .filter(|(j, v)| *j != i && !v.is_zero())
+                .collect()
+        })
+        .collect()
+}
+
+/// A partial assignment along with the coefficients of the problem restricted to its free variables
+#[derive(Clone)]
+struct Node<T> {
+    assignment: DVector<QuboType>,
+    is_free: Vec<bool>,
+    nb_free: usize,
+    constant: T,
+    linear: Vec<T>,
+    negative: Vec<T>,
+    magnitude: Vec<T>,
+}
+
+impl<T> Node<T>
+    where
+        T: QuboWeight,
+{
+    fn root(qubo_problem: &QuboProblem<T>, couplings: &[Vec<(usize, T)>]) -> Self {
+        let problem_size = qubo_problem.get_size();
+        let zero_solution = QuboSolution::zeros(problem_size);
+
+        Self {
+            assignment: DVector::zeros(problem_size),
+            is_free: vec![true; problem_size],
+            nb_free: problem_size,
+            constant: T::zero(),
+            // Flipping a bit of the zero solution costs exactly its diagonal entry
+            linear: (0..problem_size).map(|i| qubo_problem.delta_evaluate_k(&zero_solution, i)).collect(),
+            negative: couplings.iter().map(|row| row.iter().map(|(_, v)| negative_part(*v)).sum()).collect(),
+            magnitude: couplings.iter().map(|row| row.iter().map(|(_, v)| magnitude(*v)).sum()).collect(),
+        }
+    }
+
+    fn lower_bound(&self) -> T {
+        (0..self.linear.len())
+            .filter(|i| self.is_free[*i])
+            .map(|i| negative_part(self.linear[i] + self.negative[i]))
+            .fold(self.constant, |acc, x| acc + x)
+    }
+
+    fn fix(&mut self, couplings: &[Vec<(usize, T)>], v: usize, is_set: bool) {
+        self.is_free[v] = false;
+        self.nb_free -= 1;
+
+        if is_set {
+            self.constant += self.linear[v];
+            self.assignment[v] = 1;
+        }
+
+        for &(j, s) in couplings[v].iter().filter(|(j, _)| self.is_free[*j]) {
+            self.negative[j] -= negative_part(s);
+            self.magnitude[j] -= magnitude(s);
+
+            if is_set {
+                self.linear[j] += s + s;
+            }
+        }
+    }
+
+    /// Undoes [Node::fix]. Variables must be freed in the reverse order they were fixed in.
+    fn free(&mut self, couplings: &[Vec<(usize, T)>], v: usize, is_set: bool) {
+        for &(j, s) in couplings[v].iter().filter(|(j, _)| self.is_free[*j]) {
+            self.negative[j] += negative_part(s);
+            self.magnitude[j] += magnitude(s);
+
+            if is_set {
+                self.linear[j] -= s + s;
+            }
+        }
+
+        if is_set {
+            self.constant -= self.linear[v];
+            self.assignment[v] = 0;
+        }
+
+        self.is_free[v] = true;
+        self.nb_free += 1;
+    }
+}
+
+/// The state of the search of one sub tree
+struct Search<'a, T> {
+    solver: &'a BranchAndBound<T>,
+    couplings: &'a [Vec<(usize, T)>],
+    static_order: &'a [usize],
+    incumbent: &'a Mutex<(QuboSolution, T)>,
+    condition: &'a StoppingCondition<'a, T>,
+    best_evaluation: T,
+    nb_nodes: usize,
+    sub_tree: usize,
+}
+
+impl<T> Search<'_, T>
+    where
+        T: QuboWeight,
+{
+    fn next_variable(&self, node: &Node<T>) -> usize {
+        let free = |i: &usize| node.is_free[*i];
+
+        match self.solver.order {
+            VariableOrder::Index | VariableOrder::Weight => {
+                *self.static_order.iter().find(|i| free(i)).expect("A variable is free")
+            }
+            VariableOrder::Dynamic => (0..node.linear.len())
+                .filter(free)
+                .max_by(|&i, &j| {
+                    let score = |k: usize| magnitude(node.linear[k]) + node.magnitude[k];
+
+                    score(i).partial_cmp(&score(j)).unwrap_or(Ordering::Equal)
+                })
+                .expect("A variable is free"),
+        }
+    }
+
+    fn sync_incumbent(&mut self, solution: Option<&DVector<QuboType>>) {
+        let mut incumbent = self.incumbent.lock().expect("No thread panics while holding the incumbent");
+
+        if let Some(solution) = solution
+            && self.best_evaluation < incumbent.1
+        {
+            *incumbent = (QuboSolution::from(solution), self.best_evaluation);
+
+            self.solver.observers.notify_with(|| SolverEvent {
+                iteration: self.nb_nodes,
+                energy: self.best_evaluation,
+                best_energy: self.best_evaluation,
+                replica: self.sub_tree,
+                elapsed: self.condition.elapsed(),
+            });
+        }
+
+        if incumbent.1 < self.best_evaluation {
+            self.best_evaluation = incumbent.1;
+        }
+    }
+
+    fn search(&mut self, node: &mut Node<T>) {
+        self.nb_nodes += 1;
+        if self.nb_nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.condition.record_evaluations(CHECK_INTERVAL);
+            self.sync_incumbent(None);
+            self.condition.should_stop(&self.best_evaluation);
+        }
+        if self.condition.is_stopped() {
+            return;
+        }
+
+        if node.nb_free == 0 {
+            if node.constant < self.best_evaluation {
+                self.best_evaluation = node.constant;
+                self.sync_incumbent(Some(&node.assignment));
+                self.condition.should_stop(&self.best_evaluation);
+            }
+
+            return;
+        }
+
+        if node.lower_bound() >= self.best_evaluation {
+            return;
+        }
+
+        let v = self.next_variable(node);
+        // Setting a variable with a negative linear coefficient is more likely to lead to low energies
+        let first = node.linear[v] < T::zero();
+
+        for is_set in [first, !first] {
+            node.fix(self.couplings, v, is_set);
+            self.search(node);
+            node.free(self.couplings, v, is_set);
+        }
+    }
+}
+
+impl<T> Solver<QuboProblem<T>> for BranchAndBound<T>
+    where
+        T: QuboWeight,
+{
+    fn sample_until(&mut self, qubo_problem: &QuboProblem<T>, criteria: &StoppingCriteria<T>) -> SampleSet<QuboSolution, T> {
+        let problem_size = qubo_problem.get_size();
+        let couplings = couplings(qubo_problem);
+        let root = Node::root(qubo_problem, &couplings);
+
+        let static_order: Vec<usize> = match self.order {
+            VariableOrder::Index => (0..problem_size).collect(),
+            VariableOrder::Weight | VariableOrder::Dynamic => {
+                let mut order: Vec<usize> = (0..problem_size).collect();
+                let weight = |i: usize| magnitude(root.linear[i]) + root.magnitude[i];
+
+                order.sort_by(|&i, &j| weight(j).partial_cmp(&weight(i)).unwrap_or(Ordering::Equal));
+
+                order
+            }
+        };
+
+        // The tabu search only provides an incumbent, so it is kept short
+        let max_iterations = (10 * problem_size).max(100).try_into().expect("Non zero");
+        let mut tabu_search = TabuSearch::new(max_iterations, 1.try_into().expect("Non zero"));
+        if let Some(seed) = self.seed {
+            tabu_search = tabu_search.with_seed(seed);
+        }
+        // Both searches run under the same condition, so that they share the time and evaluations
+        let condition = criteria.start();
+        let seed = tabu_search
+            .search(qubo_problem, &condition)
+            .into_iter()
+            .next()
+            .expect("Solvers return at least one sample")
+            .solution;
+        let seed_evaluation = qubo_problem.evaluate(&seed);
+        debug!("Starting branch and bound from an incumbent of {}", seed_evaluation);
+
+        let incumbent = Mutex::new((seed, seed_evaluation));
+        // The number of sub trees must fit in a usize
+        let split_depth = self.split_depth.min(problem_size).min(usize::BITS as usize - 1);
+
+        (0..1usize << split_depth).into_par_iter().for_each(|prefix| {
+            let mut node = root.clone();
+            for (bit, &v) in static_order[..split_depth].iter().enumerate() {
+                node.fix(&couplings, v, prefix >> bit & 1 == 1);
+            }
+
+            let mut search = Search {
+                solver: self,
+                couplings: &couplings,
+                static_order: &static_order,
+                incumbent: &incumbent,
+                condition: &condition,
+                best_evaluation: seed_evaluation,
+                nb_nodes: 0,
+                sub_tree: prefix,
+            };
+            search.sync_incumbent(None);
+            search.search(&mut node);
+            search.condition.record_evaluations(search.nb_nodes % CHECK_INTERVAL);
+        });
+
+        let (solution, evaluation) = incumbent.into_inner().expect("No thread panics while holding the incumbent");
+
+        if condition.is_stopped() {
+            debug!("Search was stopped early with a min evaluation {} with solution: {}", evaluation, solution);
+        } else {
+            debug!("Produced a provably optimal min evaluation {} with solution: {}", evaluation, solution);
+        }
+
+        SampleSet::from_iter([(solution, evaluation, 0)])
+    }
+}
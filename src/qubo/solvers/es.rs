@@ -1,73 +1,186 @@
-use std::cmp::min_by_key;
+use std::cmp::Ordering;
+use std::num::NonZeroUsize;
 
 use log::{debug, log_enabled, warn};
 use log::Level::Warn;
-use nalgebra::DVector;
 
-use crate::core::Solver;
-use crate::qubo::{QuboProblem, QuboSolution, QuboType};
+use crate::core::{SampleSet, Solver, StoppingCondition, StoppingCriteria};
+use crate::qubo::{LocalFieldState, QuboProblem, QuboSolution, QuboWeight};
 
-pub struct ExhaustiveSearch {}
+pub struct ExhaustiveSearch {
+    initial_state: Option<QuboSolution>,
+    max_ground_states: NonZeroUsize,
+}
+
+impl Default for ExhaustiveSearch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl ExhaustiveSearch {
     pub fn new() -> Self {
-        ExhaustiveSearch {}
+        ExhaustiveSearch {
+            initial_state: None,
+            max_ground_states: DEFAULT_MAX_GROUND_STATES,
+        }
+    }
+
+    /// Starts the enumeration from this solution instead of all zeros, so that a search that is
+    /// stopped early has visited it and the solutions that only differ from it in the first bits
+    pub fn with_initial_state(mut self, initial_state: QuboSolution) -> Self {
+        self.initial_state = Some(initial_state);
+
+        self
+    }
+
+    /// The number of ground states returned, beyond which they are only counted. Defaults to 1024.
+    pub fn with_max_ground_states(mut self, max_ground_states: NonZeroUsize) -> Self {
+        self.max_ground_states = max_ground_states;
+
+        self
     }
 }
 
-#[inline]
-pub fn calculate_deltas_i(
-    problem: &QuboProblem,
-    solution: &QuboSolution,
-    old_deltas: &[QuboType],
-    i: usize,
-) -> Vec<QuboType> {
-    old_deltas
-        .iter()
-        .cloned()
-        .enumerate()
-        .take(i)
-        .map(|(j, d_j)| problem.flip_j_and_delta_evaluate_k(solution, d_j, i - 1, j))
-        .collect()
+/// The state that an enumeration starts from, all zeros unless an initial state is given
+pub(super) fn start_state<T>(qubo_problem: &QuboProblem<T>, initial_state: Option<&QuboSolution>) -> LocalFieldState<T>
+    where
+        T: QuboWeight,
+{
+    let solution = match initial_state {
+        Some(solution) => {
+            assert_eq!(qubo_problem.get_size(), solution.len(), "The initial state must have the size of the problem");
+
+            solution.clone()
+        }
+        None => QuboSolution::zeros(qubo_problem.get_size()),
+    };
+
+    LocalFieldState::new(qubo_problem, solution)
+}
+
+/// The number of ground states that the exhaustive searches keep by default
+pub(super) const DEFAULT_MAX_GROUND_STATES: NonZeroUsize = NonZeroUsize::new(1024).unwrap();
+
+/// The lowest energy found along with the solutions that reach it. Only the first `max_states` of
+/// them are kept, as a problem with `k` free variables has at least `2^k` ground states, and the
+/// others are only counted.
+#[derive(Clone, Debug)]
+pub struct GroundStates<T> {
+    pub energy: T,
+    pub states: Vec<QuboSolution>,
+    /// The number of ground states found, including the ones that were not kept
+    pub count: usize,
+    max_states: NonZeroUsize,
+}
+
+impl<T> GroundStates<T>
+    where
+        T: QuboWeight,
+{
+    pub(super) fn new(max_states: NonZeroUsize) -> Self {
+        Self {
+            energy: T::zero(),
+            states: Vec::new(),
+            count: 0,
+            max_states,
+        }
+    }
+
+    /// Adds a solution if it is at least as good as the ground states
+    pub(super) fn record(&mut self, energy: T, solution: &QuboSolution) {
+        if self.count == 0 || energy < self.energy {
+            self.energy = energy;
+            self.states.clear();
+            self.count = 0;
+        } else if energy != self.energy {
+            return;
+        }
+
+        self.count += 1;
+        if self.states.len() < self.max_states.get() {
+            self.states.push(solution.clone());
+        }
+    }
+
+    /// Keeps the ground states with the lower energy, or all of them up to the limit when both are tied
+    pub(super) fn merge(mut self, mut other: Self) -> Self {
+        if other.count == 0 {
+            return self;
+        }
+        if self.count == 0 {
+            return other;
+        }
+
+        match self.energy.partial_cmp(&other.energy).unwrap_or(Ordering::Equal) {
+            Ordering::Less => self,
+            Ordering::Greater => other,
+            Ordering::Equal => {
+                let nb_kept = self.max_states.get().saturating_sub(self.states.len());
+                self.states.extend(other.states.drain(..).take(nb_kept));
+                self.count += other.count;
+
+                self
+            }
+        }
+    }
+
+    /// The samples of the kept ground states, where solutions with more set bits come first
+    pub(super) fn into_samples(mut self) -> SampleSet<QuboSolution, T> {
+        self.states.sort_by_key(|solution| std::cmp::Reverse(solution.count_ones()));
+
+        self.states.into_iter().map(|solution| (solution, self.energy, 0)).collect()
+    }
 }
 
-/// This operation is `O(n 2^n)`
-pub fn exhaustive_search_helper(
-    problem: &QuboProblem,
-    solution: QuboSolution,
-    deltas: Vec<QuboType>,
-    curr_eval: QuboType,
+// The stopping criteria are checked once per sub-tree of size 2^STOPPING_CHECK_DEPTH
+const STOPPING_CHECK_DEPTH: usize = 10;
+
+/// Visits every value of the first `i` bits, adding the best solutions to `ground_states`, and leaves
+/// the state as it found it. Only the bits below `i` are read inside the sub-tree, so flips skip the
+/// deltas of the others, which are restored when the flips are undone. On a dense problem this makes
+/// `O(2^n)` delta updates in total.
+pub fn exhaustive_search_helper<T>(
+    problem: &QuboProblem<T>,
+    state: &mut LocalFieldState<T>,
     i: usize,
-) -> (QuboSolution, QuboType) {
+    condition: &StoppingCondition<T>,
+    ground_states: &mut GroundStates<T>,
+)
+    where
+        T: QuboWeight,
+{
     if i == 0 {
-        return (solution, curr_eval);
+        return ground_states.record(state.evaluation(), state.solution());
     }
-    let solution_i = solution.flip(i - 1);
-    let eval_i = curr_eval + deltas[i - 1];
 
-    // Update deltas
-    let new_deltas = calculate_deltas_i(problem, &solution, &deltas, i);
+    exhaustive_search_helper(problem, state, i - 1, condition, ground_states);
 
-    let left_min = exhaustive_search_helper(problem, solution, deltas, curr_eval, i - 1);
-    let right_min = exhaustive_search_helper(problem, solution_i, new_deltas, eval_i, i - 1);
+    if i == STOPPING_CHECK_DEPTH {
+        condition.record_evaluations(1 << i);
+    }
 
-    min_by_key(
-        left_min,
-        right_min,
-        |(QuboSolution(solution_vector), eval)| (*eval, -solution_vector.sum()),
-    )
+    // Only the sub-trees that have not been explored yet are skipped, so the ground states are the best so far
+    if condition.is_stopped() || (i == STOPPING_CHECK_DEPTH && condition.should_stop(&ground_states.energy)) {
+        return;
+    }
+
+    state.flip_below(problem, i - 1, i - 1);
+    exhaustive_search_helper(problem, state, i - 1, condition, ground_states);
+    state.flip_below(problem, i - 1, i - 1);
 }
 
 
-impl Solver<QuboProblem> for ExhaustiveSearch {
-    fn solve(&mut self, qubo_problem: &QuboProblem) -> QuboSolution {
+impl<T> Solver<QuboProblem<T>> for ExhaustiveSearch
+    where
+        T: QuboWeight,
+{
+    fn sample_until(&mut self, qubo_problem: &QuboProblem<T>, criteria: &StoppingCriteria<T>) -> SampleSet<QuboSolution, T> {
         const BIGGEST_REASONABLE_SEARCH_SIZE: usize = 32;
 
-        let start_solution = QuboSolution(DVector::zeros(qubo_problem.get_size()));
+        let condition = criteria.start();
 
-        let delta_j_precalcs: Vec<QuboType> = (0..qubo_problem.get_size())
-            .map(|i| qubo_problem.delta_evaluate_k(&start_solution, i))
-            .collect();
+        let mut state = start_state(qubo_problem, self.initial_state.as_ref());
 
         if log_enabled!(Warn) && qubo_problem.get_size() > BIGGEST_REASONABLE_SEARCH_SIZE {
             warn!("Exhaustive Searches greater than {BIGGEST_REASONABLE_SEARCH_SIZE} can take extremely long amounts of time! (This algorithm runs in exponential time, but it is provably optimal!)")
@@ -78,16 +191,19 @@ impl Solver<QuboProblem> for ExhaustiveSearch {
             qubo_problem.get_size()
         );
 
-        let (min_solution, min_eval) =
-            // exhaustive_search_helper(&problem, start_solution, 0, problem.get_size());
-            exhaustive_search_helper(qubo_problem, start_solution, delta_j_precalcs, 0, qubo_problem.get_size());
-
-        debug!(
-            "Produced a provably optimal min evaluation {} with solution: {}",
-            min_eval,
-            min_solution.0.transpose()
-        );
+        let mut ground_states = GroundStates::new(self.max_ground_states);
+        exhaustive_search_helper(qubo_problem, &mut state, qubo_problem.get_size(), &condition, &mut ground_states);
+
+        if condition.is_stopped() {
+            debug!("Search was stopped early with a min evaluation {} with {} solutions", ground_states.energy, ground_states.count);
+        } else {
+            debug!(
+                "Produced a provably optimal min evaluation {} with {} ground states",
+                ground_states.energy,
+                ground_states.count
+            );
+        }
 
-        min_solution
+        ground_states.into_samples()
     }
 }
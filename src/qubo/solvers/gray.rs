@@ -0,0 +1,209 @@
+use std::iter::zip;
+use std::num::NonZeroUsize;
+use std::thread::available_parallelism;
+
+use log::{debug, log_enabled, warn};
+use log::Level::Warn;
+use rayon::prelude::*;
+
+use crate::core::{SampleSet, Solver, StoppingCondition, StoppingCriteria};
+use crate::qubo::{QuboProblem, QuboSolution, QuboWeight};
+use crate::qubo::solvers::es::{DEFAULT_MAX_GROUND_STATES, GroundStates};
+
+// The stopping criteria are checked once every 2^STOPPING_CHECK_BITS steps
+const STOPPING_CHECK_BITS: u32 = 16;
+
+/// An exhaustive search that visits the solutions in Gray code order, so that consecutive solutions
+/// differ by a single bit. Each step flips that bit in a packed solution and updates the local fields
+/// of the bits below it, which takes amortised constant time and allocates nothing.
+///
+/// The values of the last `split_bits` variables are fixed up front, and the resulting ranges are
+/// searched in parallel.
+pub struct GrayCodeSearch {
+    split_bits: usize,
+    max_ground_states: NonZeroUsize,
+}
+
+impl Default for GrayCodeSearch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GrayCodeSearch {
+    pub fn new() -> Self {
+        // A few ranges per thread, so that a slow thread does not hold up the others
+        let nb_threads = available_parallelism().map_or(1, |n| n.get());
+
+        Self {
+            split_bits: nb_threads.next_power_of_two().ilog2() as usize + 2,
+            max_ground_states: DEFAULT_MAX_GROUND_STATES,
+        }
+    }
+
+    /// The number of variables fixed to split the search into `2^split_bits` ranges
+    pub fn with_split_bits(mut self, split_bits: usize) -> Self {
+        self.split_bits = split_bits;
+
+        self
+    }
+
+    /// The number of ground states returned, beyond which they are only counted. Defaults to 1024.
+    pub fn with_max_ground_states(mut self, max_ground_states: NonZeroUsize) -> Self {
+        self.max_ground_states = max_ground_states;
+
+        self
+    }
+}
+
+/// The coefficients that a Gray code step needs for each variable `j`
+struct Row<T> {
+    diagonal: T,
+    // S_(j, j-1)
+    previous: T,
+    // S_kj for k < j
+    lower: Vec<(usize, T)>,
+}
+
+/// Enumerates the `2^nb_free` solutions whose last bits are given by `prefix`.
+///
+/// Just before bit `j` is flipped, the bits below it always read `0..010`, i.e. only bit `j - 1` is
+/// set. So its delta only depends on the bits above it, whose contribution is kept up to date when
+/// they are flipped, and a flip of bit `j` only updates the `j` bits below it. This takes amortised
+/// constant time per step.
+fn search_range<T>(
+    qubo_problem: &QuboProblem<T>,
+    rows: &[Row<T>],
+    nb_free: usize,
+    prefix: usize,
+    max_ground_states: NonZeroUsize,
+    condition: &StoppingCondition<T>,
+) -> GroundStates<T>
+    where
+        T: QuboWeight,
+{
+    let problem_size = qubo_problem.get_size();
+
+    let mut bits = QuboSolution::from_fn(problem_size, |i| i >= nb_free && prefix >> (i - nb_free) & 1 == 1);
+
+    let mut energy = qubo_problem.evaluate(&bits);
+
+    // sum_{i > k} S_ki x_i
+    let mut upper_fields = vec![T::zero(); problem_size];
+    for (i, row) in rows.iter().enumerate().filter(|(i, _)| bits.get(*i)) {
+        for &(k, w_ki) in &row.lower {
+            debug_assert!(k < i);
+            upper_fields[k] += w_ki;
+        }
+    }
+
+    let mut ground_states = GroundStates::new(max_ground_states);
+    ground_states.record(energy, &bits);
+
+    for step in 1usize..1 << nb_free {
+        if step.trailing_zeros() >= STOPPING_CHECK_BITS {
+            condition.record_evaluations(1 << STOPPING_CHECK_BITS);
+
+            if condition.should_stop(&ground_states.energy) {
+                break;
+            }
+        }
+
+        // The Gray code of step differs from that of step - 1 by its lowest set bit
+        let j = step.trailing_zeros() as usize;
+        let row = &rows[j];
+        let field = if j > 0 { upper_fields[j] + row.previous } else { upper_fields[j] };
+        let change = row.diagonal + field + field;
+
+        if bits.get(j) {
+            energy -= change;
+
+            for &(k, w_kj) in &row.lower {
+                upper_fields[k] -= w_kj;
+            }
+        } else {
+            energy += change;
+
+            for &(k, w_kj) in &row.lower {
+                upper_fields[k] += w_kj;
+            }
+        }
+        bits.flip_in_place(j);
+
+        ground_states.record(energy, &bits);
+    }
+
+    ground_states
+}
+
+impl GrayCodeSearch {
+    /// Returns the lowest energy of the problem along with the solutions that reach it, up to the
+    /// limit given by [GrayCodeSearch::with_max_ground_states], and the number of them
+    pub fn ground_states<T>(&self, qubo_problem: &QuboProblem<T>, criteria: &StoppingCriteria<T>) -> GroundStates<T>
+        where
+            T: QuboWeight,
+    {
+        const BIGGEST_REASONABLE_SEARCH_SIZE: usize = 32;
+
+        let problem_size = qubo_problem.get_size();
+
+        if log_enabled!(Warn) && problem_size > BIGGEST_REASONABLE_SEARCH_SIZE + self.split_bits {
+            warn!("Exhaustive Searches greater than {BIGGEST_REASONABLE_SEARCH_SIZE} can take extremely long amounts of time! (This algorithm runs in exponential time, but it is provably optimal!)")
+        }
+
+        let rows: Vec<Row<T>> = qubo_problem
+            .get_sparse()
+            .row_iter()
+            .enumerate()
+            .map(|(j, row)| {
+                let entry = |k: usize| row.get_entry(k).map_or(T::zero(), |entry| entry.into_value());
+
+                Row {
+                    diagonal: entry(j),
+                    previous: if j > 0 { entry(j - 1) } else { T::zero() },
+                    lower: zip(row.col_indices().iter().cloned(), row.values().iter().cloned())
+                        .filter(|(k, _)| *k < j)
+                        .collect(),
+                }
+            })
+            .collect();
+
+        let split_bits = self.split_bits.min(problem_size);
+        let nb_free = problem_size - split_bits;
+        assert!(
+            nb_free < usize::BITS as usize && split_bits < usize::BITS as usize,
+            "The ranges and their number must fit in a usize"
+        );
+        let condition = criteria.start();
+
+        debug!("Starting Gray code search of 2^{} ranges of size 2^{}", split_bits, nb_free);
+
+        let ground_states = (0..1usize << split_bits)
+            .into_par_iter()
+            .map(|prefix| search_range(qubo_problem, &rows, nb_free, prefix, self.max_ground_states, &condition))
+            .reduce_with(GroundStates::merge)
+            .expect("There is at least one range");
+
+        if condition.is_stopped() {
+            debug!("Search was stopped early with a min evaluation {}", ground_states.energy);
+        } else {
+            debug!(
+                "Produced a provably optimal min evaluation {} with {} ground states",
+                ground_states.energy,
+                ground_states.count
+            );
+        }
+
+        ground_states
+    }
+}
+
+impl<T> Solver<QuboProblem<T>> for GrayCodeSearch
+    where
+        T: QuboWeight,
+{
+    fn sample_until(&mut self, qubo_problem: &QuboProblem<T>, criteria: &StoppingCriteria<T>) -> SampleSet<QuboSolution, T> {
+        // Ties are broken in the same way as the other exhaustive searches
+        self.ground_states(qubo_problem, criteria).into_samples()
+    }
+}
@@ -1,12 +1,31 @@
-pub use es::ExhaustiveSearch;
+pub use bnb::{BranchAndBound, VariableOrder};
+pub use es::{ExhaustiveSearch, GroundStates};
+pub use gray::GrayCodeSearch;
 pub use momentum::MomentumAnnealer;
+pub use pa::{PopulationAnnealer, PopulationStatistics};
 pub use pes::ParallelExhaustiveSearch;
+pub use pt::{ParallelTempering, SwapStatistics};
 pub use sa::SimulatedAnnealer;
+pub use sb::{BifurcationVariant, SimulatedBifurcation};
+pub use schedule::{Exponential, Geometric, Linear, Logarithmic, Piecewise, Schedule};
+pub use sqa::SimulatedQuantumAnnealer;
+pub use tabu::TabuSearch;
 pub use mopso::Mopso;
 
+#[cfg(test)]
+mod test;
+
+mod bnb;
 mod es;
+mod gray;
 mod momentum;
+mod pa;
 mod pes;
+mod pt;
 mod sa;
+mod sb;
+mod schedule;
+mod sqa;
+mod tabu;
 
 mod mopso;
@@ -1,26 +1,46 @@
+use std::sync::Arc;
+
 use log::{debug, trace};
-use nalgebra::{DMatrix, DVector};
+use nalgebra::DVector;
+use nalgebra_sparse::CsrMatrix;
 use rand::distributions::Bernoulli;
 use rand::prelude::*;
 use rand_distr::Gamma;
 use rayon::prelude::*;
 
-use crate::core::Solver;
-use crate::qubo::{QuboProblem, QuboSolution, QuboType};
+use crate::core::{Reduction, SampleSet, Solver, StoppingCondition, StoppingCriteria};
+use crate::ising::{IsingProblem, IsingSolution};
+use crate::ising::reductions::QuboToIsing;
+use crate::logging::{Observers, SolverEvent, SolverObserver};
+use crate::qubo::{QuboProblem, QuboSolution, QuboType, QuboWeight};
+use crate::qubo::solvers::schedule::{Linear, Logarithmic, Schedule};
+use crate::utils::{random_seed, seeded_rng, to_f64};
 
 type MaType = f32;
 
-fn power_method(matrix: &DMatrix<MaType>, epsilon: MaType, max_iter: usize) -> MaType {
-    let n = matrix.ncols();
-    let mut x = DVector::repeat(n, 1.0);
+/// `J x`, computed in parallel over the rows of `J`
+fn multiply(j_mat: &CsrMatrix<MaType>, x: &DVector<MaType>) -> DVector<MaType> {
+    DVector::from_vec((0..j_mat.nrows()).into_par_iter().map(|i| row_dot(j_mat, i, x)).collect())
+}
+
+fn row_dot(j_mat: &CsrMatrix<MaType>, i: usize, x: &DVector<MaType>) -> MaType {
+    let row = j_mat.row(i);
+
+    row.col_indices().iter().zip(row.values()).map(|(&j, &j_ij)| j_ij * x[j]).sum()
+}
+
+/// The largest eigenvalue of `J + shift I`
+fn power_method(j_mat: &CsrMatrix<MaType>, shift: MaType, epsilon: MaType, max_iter: usize) -> MaType {
+    let mut x = DVector::repeat(j_mat.nrows(), 1.0).normalize();
 
     let mut lambda: MaType = 1.0;
     let mut lambda_prev: MaType;
 
     for _ in 0..max_iter {
-        x = (matrix * x).normalize();
+        let y = multiply(j_mat, &x) + x.scale(shift);
         lambda_prev = lambda;
-        lambda = (x.transpose() * matrix * &x)[0];
+        lambda = x.dot(&y);
+        x = y.normalize();
 
         if (lambda - lambda_prev).abs() < epsilon {
             break;
@@ -30,85 +50,168 @@ fn power_method(matrix: &DMatrix<MaType>, epsilon: MaType, max_iter: usize) -> M
     lambda
 }
 
-fn largest_eigenvalue(j_mat: &DMatrix<MaType>, epsilon: MaType, max_iterations: usize) -> MaType {
-    let mut mu = j_mat.abs().row_sum().max() / 100.0;
+fn largest_eigenvalue(j_mat: &CsrMatrix<MaType>, abs_row_sums: &[MaType], epsilon: MaType, max_iterations: usize) -> MaType {
+    let mut mu = abs_row_sums.iter().cloned().fold(0.0, MaType::max) / 100.0;
 
     let mut k_largest_eigenvalue = 0.0;
     while k_largest_eigenvalue <= 0.0 {
         mu += -k_largest_eigenvalue;
-        let k_mat = j_mat + DMatrix::identity(j_mat.nrows(), j_mat.ncols()).scale(mu);
 
-        k_largest_eigenvalue = power_method(&k_mat, epsilon, max_iterations);
+        k_largest_eigenvalue = power_method(j_mat, mu, epsilon, max_iterations);
     }
 
     k_largest_eigenvalue - mu
 }
 
-pub struct MomentumAnnealer
+/// Momentum annealing (Okuyama et al., 2019). Every spin is updated in parallel from the spins of the
+/// previous iteration, while a self coupling `c_k w_i`, the momentum, keeps it close to its last
+/// value. The momentum is randomly dropped out with probability `p_k`, and the update is perturbed by
+/// noise proportional to the temperature `T_k`.
+///
+/// The couplings are kept sparse, and every iteration costs time linear in their number. Temperatures
+/// are in units of the mean of `sum_j |J_ij|` over the spins, so that the same schedule suits problems
+/// of any scale and density.
+pub struct MomentumAnnealer<T = QuboType>
 {
     max_iterations: usize,
+    temperature: Box<dyn Schedule>,
+    dropout: Box<dyn Schedule>,
+    momentum_scaling: Box<dyn Schedule>,
+    initial_state: Option<IsingSolution>,
+    seed: Option<u64>,
+    observers: Observers<T>,
 }
 
-impl MomentumAnnealer
+impl<T> MomentumAnnealer<T>
 {
-    pub fn new(max_iterations: usize) -> Self {
+    /// The schedules give `T_k`, `p_k` and `c_k` respectively
+    pub fn new(
+        max_iterations: usize,
+        temperature: impl Schedule + 'static,
+        dropout: impl Schedule + 'static,
+        momentum_scaling: impl Schedule + 'static,
+    ) -> Self {
         Self {
-            max_iterations
+            max_iterations,
+            temperature: Box::new(temperature),
+            dropout: Box::new(dropout),
+            momentum_scaling: Box::new(momentum_scaling),
+            initial_state: None,
+            seed: None,
+            observers: Observers::default(),
         }
     }
-}
 
-fn dropout(k: usize) -> MaType {
-    MaType::max(0.0, 0.5 - (k as MaType / 2000.0))
-}
+    /// Starts from these spins instead of random ones, e.g. to refine the solution of a previous run.
+    /// QUBO solutions are mapped onto spins with `s = 2x - 1`. The result is never worse than it.
+    pub fn with_initial_state(mut self, initial_state: impl Into<IsingSolution>) -> Self {
+        self.initial_state = Some(initial_state.into());
 
-fn momentum_scaling_factor(k: usize) -> MaType {
-    MaType::min(1.0, MaType::sqrt(k as MaType / 1000.0))
-}
+        self
+    }
+
+    /// Draws the random starting spins and the noise from this seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
 
-fn temperature(k: usize) -> MaType {
-    const BETA_0: MaType = 1e-6;
+        self
+    }
 
-    1.0 / (BETA_0 * MaType::ln(1.0 + k as MaType))
+    pub fn with_observer(mut self, observer: Arc<dyn SolverObserver<T>>) -> Self {
+        self.observers.push(observer);
+
+        self
+    }
 }
 
-impl Solver<QuboProblem> for MomentumAnnealer
-{
-    fn solve(&mut self, qubo_problem: &QuboProblem) -> QuboSolution {
-        let (h_bias, j_mat): (DVector<MaType>, DMatrix<MaType>) = {
-            let (q_typed_bias, q_typed_mat, _q_offset) = qubo_problem.get_ising();
+// The default schedules do not depend on the weights, so they are only defined once
+impl MomentumAnnealer {
+    /// `T_k = 1 / (β_0 ln(1 + k))` with `β_0 = 2 / 3`
+    pub fn default_temperature() -> Logarithmic {
+        Logarithmic::new(1.5)
+    }
 
-            (q_typed_bias.cast(), q_typed_mat.cast())
-        };
+    /// `p_k = max(0, 0.5 - k / 2000)`
+    pub fn default_dropout() -> Linear {
+        Linear::new(0.5, 0.0, 1_000)
+    }
 
-        trace!("Generated J-Matrix and bias {j_mat}{}", h_bias.transpose());
+    /// `c_k = min(1, sqrt(k / 1000))`
+    pub fn default_momentum_scaling() -> impl Schedule {
+        |k: usize| f64::min(1.0, f64::sqrt(k as f64 / 1_000.0))
+    }
+}
+
+impl<T> MomentumAnnealer<T>
+    where
+        T: QuboWeight,
+{
+    /// Anneals the spins of the problem, keeping the best solution according to `evaluate`. This is
+    /// the energy of the problem being solved, so that observers and stopping criteria use its units.
+    fn anneal(
+        &self,
+        ising_problem: &IsingProblem<T>,
+        condition: &StoppingCondition<T>,
+        evaluate: impl Fn(&IsingSolution) -> T,
+    ) -> SampleSet<IsingSolution, T> {
+        let problem_size = ising_problem.get_size();
+        if problem_size == 0 {
+            let solution = IsingSolution(DVector::zeros(0));
+            let evaluation = evaluate(&solution);
+
+            return SampleSet::from_iter([(solution, evaluation, 0)]);
+        }
 
-        let j_mat_sym = j_mat.transpose() + j_mat;
+        // The constant diagonal of the couplings is left out of the local fields
+        let couplings = ising_problem.get_couplings();
+        let j_mat = CsrMatrix::try_from_pattern_and_values(
+            couplings.pattern().clone(),
+            couplings.values().iter().map(|v| to_f64(*v) as MaType).collect(),
+        )
+            .expect("The pattern is unchanged")
+            .filter(|i, j, _| i != j);
+        // The annealer maximises sum_{i<j} J_ij s_i s_j + sum_i h_i s_i, so both are negated
+        let j_mat_neg = -&j_mat;
+        let h_bias: DVector<MaType> = ising_problem.get_biases().map(|x| -to_f64(x) as MaType);
+
+        trace!("Generated J-Matrix {j_mat_neg:?} and bias {}", h_bias.transpose());
+
+        let abs_row_sums: Vec<MaType> = j_mat_neg
+            .row_iter()
+            .map(|row| row.values().iter().map(|v| v.abs()).sum())
+            .collect();
 
         let max_eigenvalue: MaType =
             // According to the paper this should not take longer than 300 iterations to be close
             // enough to the real value and is thus a constant factor
-            largest_eigenvalue(&(-&j_mat_sym), 1e-6, 300);
+            largest_eigenvalue(&j_mat, &abs_row_sums, 1e-6, 300);
 
         debug!("Using maximum eigenvalue {max_eigenvalue}");
 
-        let problem_size = qubo_problem.get_size();
+        // Temperatures are relative to the typical magnitude of the local fields
+        let field_scale = abs_row_sums.iter().sum::<MaType>() / problem_size as MaType;
 
         let w = {
-            let mut w_builder = DVector::zeros(problem_size);
+            let mut w_builder: DVector<MaType> = DVector::zeros(problem_size);
             let mut c: DVector<MaType> = DVector::zeros(problem_size);
 
             for i in 0..problem_size {
-                let abs_row_sum = j_mat_sym.row(i).map(MaType::abs).sum();
-                if max_eigenvalue >= abs_row_sum {
-                    w_builder[i] = abs_row_sum;
+                if max_eigenvalue >= abs_row_sums[i] {
+                    w_builder[i] = abs_row_sums[i];
                     c[i] = 1.0;
                 } else {
                     w_builder[i] = max_eigenvalue / 2.0;
                 }
             }
 
-            let neg_vec = DVector::from_fn(problem_size, |i, _| c[i] * (j_mat_sym.row(i).map(MaType::abs) * &c)[0] / 2.0);
+            let neg_vec = DVector::from_iterator(
+                problem_size,
+                j_mat_neg.row_iter().enumerate().map(|(i, row)| {
+                    let abs_row_dot: MaType = row.col_indices().iter().zip(row.values()).map(|(&j, v)| v.abs() * c[j]).sum();
+
+                    c[i] * abs_row_dot / 2.0
+                }),
+            );
 
             w_builder -= neg_vec;
 
@@ -118,33 +221,91 @@ impl Solver<QuboProblem> for MomentumAnnealer
         let gamma = Gamma::new(1.0, 1.0)
             .unwrap();
 
-        let mut s_k: DVector<MaType> = DVector::from_distribution(problem_size, &Bernoulli::new(0.5).unwrap(), &mut thread_rng()).map(|x| if x { 1.0 } else { -1.0 });
+        let mut rng = seeded_rng(self.seed.unwrap_or_else(random_seed), 0);
+
+        let mut s_k: DVector<MaType> = match &self.initial_state {
+            Some(IsingSolution(spins)) => {
+                assert_eq!(problem_size, spins.len(), "The initial state must have the size of the problem");
+
+                spins.map(|s| s as MaType)
+            }
+            None => DVector::from_distribution(problem_size, &Bernoulli::new(0.5).unwrap(), &mut rng).map(|x| if x { 1.0 } else { -1.0 }),
+        };
         let mut s_k1: DVector<MaType> = s_k.clone();
 
+        let to_solution = |s: &DVector<MaType>| IsingSolution(s.map(|x| if x > 0.0 { 1 } else { -1 }));
+
+        let mut best_solution = to_solution(&s_k);
+        let mut best_evaluation = evaluate(&best_solution);
+
         for k in 0..=self.max_iterations {
-            let c_k = momentum_scaling_factor(k);
-            let p_k = dropout(k);
-            let t_k = temperature(k);
+            condition.record_evaluations(1);
+            if condition.should_stop(&best_evaluation) {
+                break;
+            }
+
+            let c_k = self.momentum_scaling.value(k) as MaType;
+            let p_k = self.dropout.value(k).clamp(0.0, 1.0);
+            let t_k = self.temperature.value(k) as MaType * field_scale;
 
-            let bernoulli = Bernoulli::new(p_k as f64).unwrap();
+            let bernoulli = Bernoulli::new(p_k).expect("The dropout is a probability");
 
-            s_k.par_column_iter_mut().enumerate().for_each(|(i, mut s_ki)| {
-                let mut rng = thread_rng();
-                let gamma_k = gamma.sample(&mut rng);
-                let should_drop = bernoulli.sample(&mut rng);
+            // The noise is drawn up front, so that it does not depend on how the spins are scheduled
+            let noise: Vec<(MaType, bool)> = (0..problem_size)
+                .map(|_| (gamma.sample(&mut rng), bernoulli.sample(&mut rng)))
+                .collect();
 
-                let jsk: MaType = (j_mat_sym.row(i) * &s_k1)[0] + if should_drop { 0.0 } else { w[i] * s_k1[i] * c_k };
+            s_k.as_mut_slice().par_iter_mut().zip(&noise).enumerate().for_each(|(i, (s_ki, &(gamma_k, should_drop)))| {
+                let jsk: MaType = row_dot(&j_mat_neg, i, &s_k1) + if should_drop { 0.0 } else { w[i] * s_k1[i] * c_k };
 
-                s_ki[0] = (h_bias[i] + jsk - (t_k / 2.0) * gamma_k * s_ki[0]).signum()
+                *s_ki = (h_bias[i] + jsk - (t_k / 2.0) * gamma_k * *s_ki).signum()
             });
 
             (s_k, s_k1) = (s_k1, s_k);
+
+            let solution = to_solution(&s_k1);
+            let evaluation = evaluate(&solution);
+            if evaluation < best_evaluation {
+                best_solution = solution;
+                best_evaluation = evaluation;
+            }
+
+            self.observers.notify_with(|| SolverEvent {
+                iteration: k,
+                energy: evaluation,
+                best_energy: best_evaluation,
+                replica: 0,
+                elapsed: condition.elapsed(),
+            });
         }
 
-        let final_solution = s_k.map(|x| (x as QuboType + 1) / 2);
+        debug!("Final Evaluation is {} with solution {}", best_evaluation, best_solution);
+
+        SampleSet::from_iter([(best_solution, best_evaluation, 0)])
+    }
+}
 
-        debug!("Final Evaluation is {}", final_solution.transpose());
+impl<T> Solver<IsingProblem<T>> for MomentumAnnealer<T>
+    where
+        T: QuboWeight,
+{
+    fn sample_until(&mut self, ising_problem: &IsingProblem<T>, criteria: &StoppingCriteria<T>) -> SampleSet<IsingSolution, T> {
+        let condition = criteria.start();
+
+        self.anneal(ising_problem, &condition, |solution| ising_problem.evaluate(solution))
+    }
+}
+
+impl<T> Solver<QuboProblem<T>> for MomentumAnnealer<T>
+    where
+        T: QuboWeight,
+{
+    fn sample_until(&mut self, qubo_problem: &QuboProblem<T>, criteria: &StoppingCriteria<T>) -> SampleSet<QuboSolution, T> {
+        let condition = criteria.start();
+        let (ising_problem, _) = QuboToIsing::reduce(qubo_problem);
 
-        QuboSolution(final_solution)
+        // The spins are already evaluated on the QUBO, so only the solutions are mapped
+        self.anneal(&ising_problem, &condition, |solution| qubo_problem.evaluate(&solution.clone().into()))
+            .map(|solution, evaluation| (solution.into(), evaluation))
     }
 }
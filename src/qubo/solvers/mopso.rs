@@ -1,16 +1,217 @@
-use crate::core::Solver;
-use crate::qubo::{QuboProblem, QuboSolution};
+use std::cmp::Ordering;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
 
-pub struct Mopso;
+use log::debug;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use rayon::prelude::*;
 
-impl Mopso {
-    pub fn new() -> Self {
-        Self {}
+use crate::core::{SampleSet, Solver, StoppingCriteria};
+use crate::logging::{Observers, SolverEvent, SolverObserver};
+use crate::qubo::{QuboProblem, QuboSolution, QuboType, QuboWeight};
+use crate::utils::{random_seed, seeded_rng};
+
+const COGNITIVE_WEIGHT: f64 = 1.5;
+const SOCIAL_WEIGHT: f64 = 1.5;
+// Keeps sigmoid(v) away from 0 and 1 so that every bit always has a chance of flipping
+const MAX_VELOCITY: f64 = 4.0;
+
+/// The objectives minimised by the swarm: the energy of the solution, and the negated number of
+/// set bits (the same tie-breaker used by the exhaustive searches).
+type Objectives<T> = (T, QuboType);
+
+fn objectives<T>(qubo_problem: &QuboProblem<T>, solution: &QuboSolution) -> Objectives<T>
+    where
+        T: QuboWeight,
+{
+    (qubo_problem.evaluate(solution), -(solution.count_ones() as QuboType))
+}
+
+fn dominates<T>((a_0, a_1): Objectives<T>, (b_0, b_1): Objectives<T>) -> bool
+    where
+        T: QuboWeight,
+{
+    a_0 <= b_0 && a_1 <= b_1 && (a_0 < b_0 || a_1 < b_1)
+}
+
+fn compare_objectives<T>((a_0, a_1): &Objectives<T>, (b_0, b_1): &Objectives<T>) -> Ordering
+    where
+        T: QuboWeight,
+{
+    a_0.partial_cmp(b_0).unwrap_or(Ordering::Equal).then(a_1.cmp(b_1))
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + f64::exp(-x))
+}
+
+struct Particle<T> {
+    position: QuboSolution,
+    velocity: Vec<f64>,
+    objectives: Objectives<T>,
+    best_position: QuboSolution,
+    best_objectives: Objectives<T>,
+    rng: StdRng,
+}
+
+/// A binary multi-objective particle swarm optimiser.
+///
+/// Velocities are real valued and are mapped onto bit probabilities through a sigmoid. Leaders are
+/// drawn at random from an archive of the non-dominated solutions found so far.
+pub struct Mopso<T = QuboType> {
+    swarm_size: NonZeroUsize,
+    inertia: f64,
+    max_iterations: NonZeroUsize,
+    seed: Option<u64>,
+    observers: Observers<T>,
+}
+
+impl<T> Mopso<T> {
+    pub fn new(swarm_size: NonZeroUsize, inertia: f64, max_iterations: NonZeroUsize) -> Self {
+        Self {
+            swarm_size,
+            inertia,
+            max_iterations,
+            seed: None,
+            observers: Observers::default(),
+        }
+    }
+
+    /// Gives every particle its own random stream of this seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+
+        self
+    }
+
+    pub fn with_observer(mut self, observer: Arc<dyn SolverObserver<T>>) -> Self {
+        self.observers.push(observer);
+
+        self
     }
 }
 
-impl Solver<QuboProblem> for Mopso {
-    fn solve(&mut self, _problem: &QuboProblem) -> QuboSolution {
-        unimplemented!()
+/// A non-dominated solution, along with the particle that found it
+type ArchiveEntry<T> = (QuboSolution, Objectives<T>, usize);
+
+/// Inserts a candidate into the Pareto archive, removing any solutions it dominates
+fn update_archive<T>(archive: &mut Vec<ArchiveEntry<T>>, candidate: &QuboSolution, candidate_objectives: Objectives<T>, replica: usize)
+    where
+        T: QuboWeight,
+{
+    if archive
+        .iter()
+        .any(|(solution, o, _)| *o == candidate_objectives && solution == candidate || dominates(*o, candidate_objectives))
+    {
+        return;
     }
-}
\ No newline at end of file
+
+    archive.retain(|(_, o, _)| !dominates(candidate_objectives, *o));
+    archive.push((candidate.clone(), candidate_objectives, replica));
+}
+
+impl<T> Solver<QuboProblem<T>> for Mopso<T>
+    where
+        T: QuboWeight,
+{
+    fn sample_until(&mut self, qubo_problem: &QuboProblem<T>, criteria: &StoppingCriteria<T>) -> SampleSet<QuboSolution, T> {
+        let condition = criteria.start();
+        let problem_size = qubo_problem.get_size();
+        let seed = self.seed.unwrap_or_else(random_seed);
+
+        let mut swarm: Vec<Particle<T>> = (0..self.swarm_size.get())
+            .into_par_iter()
+            .map(|replica| {
+                let mut rng = seeded_rng(seed, replica as u64);
+                let position = QuboSolution::from_fn(problem_size, |_| rng.gen_range(0..=1) == 1);
+                let velocity = (0..problem_size)
+                    .map(|_| rng.gen_range(-MAX_VELOCITY..=MAX_VELOCITY))
+                    .collect();
+                let objectives = objectives(qubo_problem, &position);
+
+                Particle {
+                    best_position: position.clone(),
+                    best_objectives: objectives,
+                    position,
+                    velocity,
+                    objectives,
+                    rng,
+                }
+            })
+            .collect();
+
+        condition.record_evaluations(swarm.len());
+
+        let mut archive = Vec::new();
+        for (replica, particle) in swarm.iter().enumerate() {
+            update_archive(&mut archive, &particle.position, particle.objectives, replica);
+        }
+
+        let max_k = self.max_iterations.get();
+        for k in 0..max_k {
+            let (_, (best_energy, _), _) = *archive
+                .iter()
+                .min_by(|(_, a, _), (_, b, _)| compare_objectives(a, b))
+                .expect("Archive cannot be empty!");
+
+            if condition.should_stop(&best_energy) {
+                break;
+            }
+
+            swarm.par_iter_mut().enumerate().for_each(|(replica, particle)| {
+                let rng = &mut particle.rng;
+                let (leader, _, _) = archive
+                    .choose(rng)
+                    .expect("Archive cannot be empty!");
+
+                for i in 0..problem_size {
+                    let x_i = particle.position.value(i) as f64;
+                    let cognitive = COGNITIVE_WEIGHT * rng.gen_range(0.0..1.0) * (particle.best_position.value(i) as f64 - x_i);
+                    let social = SOCIAL_WEIGHT * rng.gen_range(0.0..1.0) * (leader.value(i) as f64 - x_i);
+
+                    let v_i = (self.inertia * particle.velocity[i] + cognitive + social)
+                        .clamp(-MAX_VELOCITY, MAX_VELOCITY);
+
+                    particle.velocity[i] = v_i;
+                    particle.position.set(i, rng.gen_bool(sigmoid(v_i)));
+                }
+
+                particle.objectives = objectives(qubo_problem, &particle.position);
+
+                // Non-dominated personal bests are replaced at random to keep the swarm diverse
+                if dominates(particle.objectives, particle.best_objectives)
+                    || (!dominates(particle.best_objectives, particle.objectives) && rng.gen_bool(0.5))
+                {
+                    particle.best_position = particle.position.clone();
+                    particle.best_objectives = particle.objectives;
+                }
+
+                self.observers.notify_with(|| SolverEvent {
+                    iteration: k,
+                    energy: particle.objectives.0,
+                    best_energy: if particle.objectives.0 < best_energy { particle.objectives.0 } else { best_energy },
+                    replica,
+                    elapsed: condition.elapsed(),
+                });
+            });
+
+            condition.record_evaluations(swarm.len());
+
+            for (replica, particle) in swarm.iter().enumerate() {
+                update_archive(&mut archive, &particle.position, particle.objectives, replica);
+            }
+        }
+
+        // The whole Pareto front is returned, with the same tie-breaker as the exhaustive searches first
+        archive.sort_by(|(_, a, _), (_, b, _)| compare_objectives(a, b));
+        let (best_solution, (best_energy, _), _) = &archive[0];
+
+        debug!("Pareto archive produced a min evaluation {} with solution: {}", best_energy, best_solution);
+
+        archive
+            .into_iter()
+            .map(|(solution, (energy, _), replica)| (solution, energy, replica))
+            .collect()
+    }
+}
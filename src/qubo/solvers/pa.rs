@@ -0,0 +1,257 @@
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use log::{debug, trace};
+use rand::Rng;
+use rand::rngs::StdRng;
+use rayon::prelude::*;
+
+use crate::core::{SampleSet, Solver, StoppingCriteria};
+use crate::logging::{Observers, SolverEvent, SolverObserver};
+use crate::qubo::{QuboProblem, QuboSolution, QuboType, QuboWeight};
+use crate::qubo::solvers::pt::{lowest_evaluation, Replica, temperature_range};
+use crate::utils::{random_seed, seeded_rng, to_f64};
+
+/// Population annealing (Hukushima and Iba, 2003; Machta, 2010). A population of solutions, drawn
+/// uniformly at first, is cooled by raising the inverse temperature `β` linearly over `nb_steps`
+/// steps. At each step, the population is first resampled so that every solution is copied in
+/// proportion to its reweighting factor `exp(-(β' - β) E)`, and then every copy runs Metropolis
+/// sweeps in parallel at the new temperature.
+///
+/// The mean reweighting factors also estimate the partition function, and from it the free energy
+/// and the degeneracy of the ground state, which are kept in [PopulationStatistics].
+///
+/// Temperatures and energies are on the scale of [QuboProblem::evaluate]. Unless it is given, the
+/// final temperature is one at which the smallest uphill move is rarely accepted.
+pub struct PopulationAnnealer<T = QuboType>
+{
+    nb_steps: NonZeroUsize,
+    population_size: NonZeroUsize,
+    sweeps_per_step: NonZeroUsize,
+    final_temperature: Option<f64>,
+    statistics: Option<PopulationStatistics>,
+    seed: Option<u64>,
+    observers: Observers<T>,
+}
+
+/// The thermodynamic estimates of a run, at the last temperature that it reached
+#[derive(Clone, Debug)]
+pub struct PopulationStatistics {
+    pub temperature: f64,
+    /// The logarithm of the partition function `Z = sum_x exp(-E(x) / T)`
+    pub log_partition: f64,
+    /// `F = -T ln Z`
+    pub free_energy: f64,
+    /// The lowest energy that was found
+    pub ground_energy: f64,
+    /// The number of solutions at the lowest energy, estimated from the fraction of the population
+    /// that reaches it as `ρ_0 Z exp(E_0 / T)`
+    pub degeneracy: f64,
+}
+
+impl<T> PopulationAnnealer<T>
+{
+    pub fn new(nb_steps: NonZeroUsize, population_size: NonZeroUsize) -> Self {
+        Self {
+            nb_steps,
+            population_size,
+            sweeps_per_step: NonZeroUsize::MIN,
+            final_temperature: None,
+            statistics: None,
+            seed: None,
+            observers: Observers::default(),
+        }
+    }
+
+    /// The number of Metropolis sweeps run after each resampling. Defaults to `1`.
+    pub fn with_sweeps_per_step(mut self, sweeps_per_step: NonZeroUsize) -> Self {
+        self.sweeps_per_step = sweeps_per_step;
+
+        self
+    }
+
+    pub fn with_final_temperature(mut self, final_temperature: f64) -> Self {
+        assert!(final_temperature > 0.0, "Temperatures must be positive");
+
+        self.final_temperature = Some(final_temperature);
+
+        self
+    }
+
+    /// Gives every member of the population its own random stream of this seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+
+        self
+    }
+
+    pub fn with_observer(mut self, observer: Arc<dyn SolverObserver<T>>) -> Self {
+        self.observers.push(observer);
+
+        self
+    }
+
+    /// The thermodynamic estimates of the last run, if there was one
+    pub fn statistics(&self) -> Option<&PopulationStatistics> {
+        self.statistics.as_ref()
+    }
+}
+
+/// Resamples the population systematically with weights `exp(-delta_beta E)`, keeping its size. Returns
+/// the new population along with the logarithm of the mean weight.
+fn resample<T>(population: &[Replica<T>], delta_beta: f64, rng: &mut impl Rng) -> (Vec<Replica<T>>, f64)
+    where
+        T: QuboWeight,
+{
+    let population_size = population.len();
+    let min_energy = population
+        .iter()
+        .map(|member| to_f64(member.evaluation()))
+        .fold(f64::INFINITY, f64::min);
+
+    // Weights are shifted by the lowest energy so that the largest is 1
+    let weights: Vec<f64> = population
+        .iter()
+        .map(|member| f64::exp(-delta_beta * (to_f64(member.evaluation()) - min_energy)))
+        .collect();
+    let total_weight: f64 = weights.iter().sum();
+
+    let spacing = total_weight / population_size as f64;
+    let mut pointer = rng.gen_range(0.0..spacing);
+    let mut cumulative_weight = 0.0;
+    let mut resampled = Vec::with_capacity(population_size);
+
+    for (member, weight) in population.iter().zip(&weights) {
+        cumulative_weight += weight;
+
+        while pointer < cumulative_weight && resampled.len() < population_size {
+            resampled.push(member.clone());
+            pointer += spacing;
+        }
+    }
+
+    // Rounding can leave the last pointer just past the total weight
+    while resampled.len() < population_size {
+        resampled.push(population[population_size - 1].clone());
+    }
+
+    (resampled, f64::ln(spacing) - delta_beta * min_energy)
+}
+
+fn log_distribution<T>(population: &[Replica<T>], temperature: f64)
+    where
+        T: QuboWeight,
+{
+    let energies: Vec<f64> = population.iter().map(|member| to_f64(member.evaluation())).collect();
+    let mean = energies.iter().sum::<f64>() / energies.len() as f64;
+    let variance = energies.iter().map(|e| (e - mean) * (e - mean)).sum::<f64>() / energies.len() as f64;
+    let min = energies.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = energies.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+    trace!(
+        "Population at temperature {temperature} has energies in [{min}, {max}] with mean {mean} and standard deviation {}",
+        variance.sqrt()
+    );
+}
+
+impl<T> Solver<QuboProblem<T>> for PopulationAnnealer<T>
+    where
+        T: QuboWeight,
+{
+    fn sample_until(&mut self, qubo_problem: &QuboProblem<T>, criteria: &StoppingCriteria<T>) -> SampleSet<QuboSolution, T> {
+        let condition = criteria.start();
+        let seed = self.seed.unwrap_or_else(random_seed);
+        let mut rng = seeded_rng(seed, 0);
+        // Each position in the population sweeps with its own stream, whichever copy is at it
+        let mut sweep_rngs: Vec<StdRng> = (1..=self.population_size.get() as u64).map(|stream| seeded_rng(seed, stream)).collect();
+
+        let problem_size = qubo_problem.get_size();
+        let final_temperature = self.final_temperature.unwrap_or_else(|| temperature_range(qubo_problem).0);
+        let final_beta = 1.0 / final_temperature;
+
+        let mut population: Vec<_> = (0..self.population_size.get())
+            .map(|_| Replica::new(qubo_problem, &mut rng))
+            .collect();
+        // The best solution seen at each position in the population, whichever copy was at it
+        let mut position_bests: Vec<_> = population.iter().map(|member| (member.solution().clone(), member.evaluation())).collect();
+        let mut best_evaluation = lowest_evaluation(&position_bests);
+
+        // Every solution is equally likely at infinite temperature, where Z = 2^n
+        let mut beta = 0.0;
+        let mut log_partition = problem_size as f64 * f64::ln(2.0);
+
+        let max_k = self.nb_steps.get();
+        let mut steps = max_k;
+        for k in 0..max_k {
+            for (i, member) in population.iter().enumerate() {
+                self.observers.notify_with(|| SolverEvent {
+                    iteration: k,
+                    energy: member.evaluation(),
+                    best_energy: best_evaluation,
+                    replica: i,
+                    elapsed: condition.elapsed(),
+                });
+            }
+
+            condition.record_evaluations(population.len() * self.sweeps_per_step.get() * problem_size);
+            if condition.should_stop(&best_evaluation) {
+                steps = k;
+                break;
+            }
+
+            let next_beta = final_beta * (k + 1) as f64 / max_k as f64;
+            let (resampled, log_mean_weight) = resample(&population, next_beta - beta, &mut rng);
+            population = resampled;
+            log_partition += log_mean_weight;
+            beta = next_beta;
+
+            for _ in 0..self.sweeps_per_step.get() {
+                population
+                    .par_iter_mut()
+                    .zip(&mut sweep_rngs)
+                    .zip(&mut position_bests)
+                    .for_each(|((member, rng), position_best)| {
+                        if let Some((solution, evaluation)) = member.sweep(qubo_problem, 1.0 / beta, rng)
+                            && evaluation < position_best.1
+                        {
+                            *position_best = (solution, evaluation);
+                        }
+                    });
+                best_evaluation = lowest_evaluation(&position_bests);
+            }
+
+            log_distribution(&population, 1.0 / beta);
+        }
+
+        for (i, member) in population.iter().enumerate() {
+            self.observers.notify_with(|| SolverEvent {
+                iteration: steps,
+                energy: member.evaluation(),
+                best_energy: best_evaluation,
+                replica: i,
+                elapsed: condition.elapsed(),
+            });
+        }
+
+        let ground_energy = to_f64(best_evaluation);
+        let ground_fraction = population.iter().filter(|member| member.evaluation() == best_evaluation).count() as f64
+            / population.len() as f64;
+
+        // Nothing is known beyond Z = 2^n when the run stopped before cooling at all
+        let statistics = PopulationStatistics {
+            temperature: 1.0 / beta,
+            log_partition,
+            free_energy: -log_partition / beta,
+            ground_energy,
+            degeneracy: if beta > 0.0 { ground_fraction * f64::exp(log_partition + beta * ground_energy) } else { f64::NAN },
+        };
+        debug!("Population annealing estimated {:?}", statistics);
+        self.statistics = Some(statistics);
+
+        position_bests
+            .into_iter()
+            .enumerate()
+            .map(|(position, (solution, evaluation))| (solution, evaluation, position))
+            .collect()
+    }
+}
@@ -3,56 +3,78 @@ use std::thread::available_parallelism;
 
 use log::{debug, log_enabled, warn};
 use log::Level::Warn;
-use nalgebra::DVector;
 use rayon::prelude::*;
 
-use crate::core::Solver;
-use crate::qubo::{QuboProblem, QuboSolution, QuboType};
-use crate::qubo::solvers::es::{calculate_deltas_i, exhaustive_search_helper};
+use crate::core::{SampleSet, Solver, StoppingCriteria};
+use crate::qubo::{LocalFieldState, QuboProblem, QuboSolution, QuboWeight};
+use crate::qubo::solvers::es::{DEFAULT_MAX_GROUND_STATES, exhaustive_search_helper, GroundStates, start_state};
 
 pub struct ParallelExhaustiveSearch {
     beta: NonZeroUsize,
+    initial_state: Option<QuboSolution>,
+    max_ground_states: NonZeroUsize,
 }
 
 
 impl ParallelExhaustiveSearch {
     pub fn new(beta: NonZeroUsize) -> Self {
-        Self { beta }
+        Self {
+            beta,
+            initial_state: None,
+            max_ground_states: DEFAULT_MAX_GROUND_STATES,
+        }
+    }
+
+    /// Starts the enumeration from this solution instead of all zeros, so that a search that is
+    /// stopped early has visited it and the solutions that only differ from it in the first bits
+    pub fn with_initial_state(mut self, initial_state: QuboSolution) -> Self {
+        self.initial_state = Some(initial_state);
+
+        self
+    }
+
+    /// The number of ground states returned, beyond which they are only counted. Defaults to 1024.
+    pub fn with_max_ground_states(mut self, max_ground_states: NonZeroUsize) -> Self {
+        self.max_ground_states = max_ground_states;
+
+        self
     }
 }
 
 // Generate all bit strings and deltas for computation. Produces an array of size 2^n
 // Can technically generate all solutions for a problem
-// This operation runs in O(n^3) time sequentially, but it is technically O(n^2) across 2^n processors
-// Think about it this way: this code runs an O(n) operation, across (2^n) processors,O(n - alpha) times
-fn generate_prefixes(
-    problem: &QuboProblem,
-    solution_list: Vec<(QuboSolution, Vec<QuboType>, QuboType)>,
+// Each state is split in two by flipping bit i - 1, in O(d) time for a bit of degree d
+fn generate_prefixes<T>(
+    problem: &QuboProblem<T>,
+    states: Vec<LocalFieldState<T>>,
     min_i: usize,
     i: usize,
-) -> Vec<(QuboSolution, Vec<QuboType>, QuboType)> {
+) -> Vec<LocalFieldState<T>>
+    where
+        T: QuboWeight,
+{
     if i <= min_i {
-        return solution_list;
+        return states;
     }
 
-    let new_solutions = solution_list
+    let new_states = states
         .into_par_iter()
-        .flat_map(|(solution, mut deltas, eval)| {
-            let deltas_i: Vec<_> = calculate_deltas_i(problem, &solution, &deltas, i);
-            let eval_i = eval + deltas[i - 1];
-            let solution_i = solution.flip(i - 1);
+        .flat_map(|state| {
+            let mut state_i = state.clone();
+            state_i.flip(problem, i - 1);
 
-            deltas.truncate(deltas_i.len());
-
-            [(solution, deltas, eval), (solution_i, deltas_i, eval_i)]
+            [state, state_i]
         })
         .collect();
 
-    generate_prefixes(problem, new_solutions, min_i, i - 1)
+    generate_prefixes(problem, new_states, min_i, i - 1)
 }
 
-impl Solver<QuboProblem> for ParallelExhaustiveSearch {
-    fn solve(&mut self, qubo_problem: &QuboProblem) -> QuboSolution {
+impl<T> Solver<QuboProblem<T>> for ParallelExhaustiveSearch
+    where
+        T: QuboWeight,
+{
+    fn sample_until(&mut self, qubo_problem: &QuboProblem<T>, criteria: &StoppingCriteria<T>) -> SampleSet<QuboSolution, T> {
         const BIGGEST_REASONABLE_SEARCH_SIZE: usize = 32;
 
         if log_enabled!(Warn)
@@ -61,41 +83,41 @@ impl Solver<QuboProblem> for ParallelExhaustiveSearch {
             warn!("Exhaustive Searches greater than {BIGGEST_REASONABLE_SEARCH_SIZE} can take extremely long amounts of time! (This algorithm runs in exponential time, but it is provably optimal!)")
         }
 
-        let start_solution = QuboSolution(DVector::zeros(qubo_problem.get_size()));
-        let delta_j_precalcs: Vec<QuboType> = (0..qubo_problem.get_size())
-            .map(|i| qubo_problem.delta_evaluate_k(&start_solution, i))
-            .collect();
+        let condition = criteria.start();
 
-        let mut solution_list = vec![(start_solution, delta_j_precalcs, 0)];
+        let start = start_state(qubo_problem, self.initial_state.as_ref());
 
-        let sub_tree_size = qubo_problem.get_size() + 1 - self.beta.get();
-        solution_list = generate_prefixes(
-            qubo_problem,
-            solution_list,
-            sub_tree_size,
-            qubo_problem.get_size(),
-        );
+        // Problems smaller than the prefix are searched entirely through the prefixes
+        let sub_tree_size = (qubo_problem.get_size() + 1).saturating_sub(self.beta.get());
+        let states = generate_prefixes(qubo_problem, vec![start], sub_tree_size, qubo_problem.get_size());
 
         debug!(
             "Starting parallel search across {} processors of tree of size 2^{}",
-            solution_list.len(),
+            states.len(),
             sub_tree_size
         );
 
-        let (min_solution, min_eval) = solution_list
+        let ground_states = states
             .into_par_iter()
-            .map(|(solution, deltas, eval)| {
-                exhaustive_search_helper(qubo_problem, solution, deltas, eval, sub_tree_size)
+            .map(|mut state| {
+                let mut ground_states = GroundStates::new(self.max_ground_states);
+                exhaustive_search_helper(qubo_problem, &mut state, sub_tree_size, &condition, &mut ground_states);
+
+                ground_states
             })
-            .min_by_key(|(QuboSolution(solution), eval)| (*eval, -solution.sum()))
+            .reduce_with(GroundStates::merge)
             .expect("Solution list cannot be empty");
 
-        debug!(
-            "Produced a provably optimal min evaluation {} with solution: {}",
-            min_eval,
-            min_solution.0.transpose()
-        );
+        if condition.is_stopped() {
+            debug!("Search was stopped early with a min evaluation {} with {} solutions", ground_states.energy, ground_states.count);
+        } else {
+            debug!(
+                "Produced a provably optimal min evaluation {} with {} ground states",
+                ground_states.energy,
+                ground_states.count
+            );
+        }
 
-        min_solution
+        ground_states.into_samples()
     }
 }
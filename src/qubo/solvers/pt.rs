@@ -0,0 +1,380 @@
+use std::cmp::Ordering;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use log::debug;
+use rand::Rng;
+use rand::rngs::StdRng;
+use rayon::prelude::*;
+
+use crate::core::{SampleSet, Solver, StoppingCriteria};
+use crate::logging::{Observers, SolverEvent, SolverObserver};
+use crate::qubo::{LocalFieldState, QuboProblem, QuboSolution, QuboType, QuboWeight};
+use crate::qubo::solvers::sa::starting_solution;
+use crate::utils::{random_seed, seeded_rng, to_f64};
+
+// Swap rounds between two adjustments of an adaptive ladder
+const ADAPTATION_INTERVAL: usize = 20;
+
+/// Replica exchange Monte Carlo. A ladder of replicas is kept at fixed temperatures, each one running
+/// Metropolis sweeps in parallel, and after every `swap_interval` sweeps neighbouring temperatures
+/// try to exchange their solutions. Good solutions found at high temperatures can thus sink down
+/// the ladder, while the coldest replicas can escape local minima by climbing back up.
+///
+/// Temperatures are on the scale of [QuboProblem::evaluate]. Unless a ladder is given, a geometric
+/// one is built from the problem coefficients.
+pub struct ParallelTempering<T = QuboType>
+{
+    max_sweeps: NonZeroUsize,
+    nb_replicas: NonZeroUsize,
+    temperatures: Option<Vec<f64>>,
+    swap_interval: NonZeroUsize,
+    adaptive: bool,
+    statistics: Option<SwapStatistics>,
+    initial_states: Vec<QuboSolution>,
+    seed: Option<u64>,
+    observers: Observers<T>,
+}
+
+/// The swaps attempted and accepted between each pair of neighbouring temperatures during a run,
+/// along with the final ladder
+#[derive(Clone, Debug)]
+pub struct SwapStatistics {
+    pub temperatures: Vec<f64>,
+    pub attempted: Vec<usize>,
+    pub accepted: Vec<usize>,
+}
+
+impl SwapStatistics {
+    fn new(temperatures: Vec<f64>) -> Self {
+        let nb_pairs = temperatures.len() - 1;
+
+        Self {
+            temperatures,
+            attempted: vec![0; nb_pairs],
+            accepted: vec![0; nb_pairs],
+        }
+    }
+
+    /// The fraction of accepted swaps between temperatures `i` and `i + 1`
+    pub fn acceptance_rates(&self) -> Vec<f64> {
+        self.attempted
+            .iter()
+            .zip(&self.accepted)
+            .map(|(&attempted, &accepted)| if attempted == 0 { 0.0 } else { accepted as f64 / attempted as f64 })
+            .collect()
+    }
+}
+
+impl<T> ParallelTempering<T>
+{
+    pub fn new(max_sweeps: NonZeroUsize, nb_replicas: NonZeroUsize) -> Self {
+        Self {
+            max_sweeps,
+            nb_replicas,
+            temperatures: None,
+            swap_interval: NonZeroUsize::MIN,
+            adaptive: true,
+            statistics: None,
+            initial_states: Vec::new(),
+            seed: None,
+            observers: Observers::default(),
+        }
+    }
+
+    /// Uses the given temperatures, sorted from coldest to hottest, instead of building a ladder from
+    /// the problem. This replaces the number of replicas given to [ParallelTempering::new].
+    pub fn with_temperatures(mut self, temperatures: Vec<f64>) -> Self {
+        assert!(!temperatures.is_empty(), "The ladder needs at least one temperature");
+        assert!(temperatures.iter().all(|t| *t > 0.0), "Temperatures must be positive");
+        assert!(temperatures.windows(2).all(|pair| pair[0] < pair[1]), "Temperatures must be strictly increasing");
+
+        self.nb_replicas = NonZeroUsize::new(temperatures.len()).unwrap();
+        self.temperatures = Some(temperatures);
+
+        self
+    }
+
+    /// The number of sweeps between two rounds of swaps
+    pub fn with_swap_interval(mut self, swap_interval: NonZeroUsize) -> Self {
+        self.swap_interval = swap_interval;
+
+        self
+    }
+
+    /// Whether the inner temperatures are moved during the first half of the run to even out the
+    /// swap acceptance rates. The coldest and hottest temperatures never change. Enabled by default.
+    pub fn with_adaptation(mut self, adaptive: bool) -> Self {
+        self.adaptive = adaptive;
+
+        self
+    }
+
+    /// Starts the replicas from these solutions instead of random ones, e.g. to refine the samples of
+    /// a previous run. The replica at the `i`-th coldest temperature starts from solution `i mod n`.
+    pub fn with_initial_states(mut self, initial_states: impl IntoIterator<Item=QuboSolution>) -> Self {
+        self.initial_states = initial_states.into_iter().collect();
+
+        self
+    }
+
+    /// Gives every temperature its own random stream of this seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+
+        self
+    }
+
+    pub fn with_observer(mut self, observer: Arc<dyn SolverObserver<T>>) -> Self {
+        self.observers.push(observer);
+
+        self
+    }
+
+    /// The swap statistics of the last run, if there was one
+    pub fn statistics(&self) -> Option<&SwapStatistics> {
+        self.statistics.as_ref()
+    }
+}
+
+/// A temperature at which the smallest uphill move is rarely accepted, and one at which even the
+/// largest is accepted half of the time
+pub(super) fn temperature_range<T>(qubo_problem: &QuboProblem<T>) -> (f64, f64)
+    where
+        T: QuboWeight,
+{
+    let q_matrix = qubo_problem.get_sparse();
+
+    let min_coefficient = q_matrix
+        .values()
+        .iter()
+        .map(|v| to_f64(*v).abs())
+        .filter(|v| *v > 0.0)
+        .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    let max_delta = q_matrix
+        .row_iter()
+        .map(|row| row.values().iter().map(|v| 2.0 * to_f64(*v).abs()).sum::<f64>())
+        .max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    match (min_coefficient, max_delta) {
+        (Some(min_coefficient), Some(max_delta)) => {
+            (min_coefficient / f64::ln(100.0), f64::max(max_delta / f64::ln(2.0), min_coefficient))
+        }
+        _ => (1.0, 1.0),
+    }
+}
+
+/// A geometric ladder spanning [temperature_range]
+fn default_ladder<T>(qubo_problem: &QuboProblem<T>, nb_replicas: usize) -> Vec<f64>
+    where
+        T: QuboWeight,
+{
+    let (t_min, t_max) = temperature_range(qubo_problem);
+
+    geometric_ladder(t_min, t_max, nb_replicas)
+}
+
+fn geometric_ladder(t_min: f64, t_max: f64, nb_replicas: usize) -> Vec<f64> {
+    if nb_replicas == 1 {
+        return vec![t_min];
+    }
+
+    let ratio = f64::powf(t_max / t_min, 1.0 / (nb_replicas - 1) as f64);
+
+    (0..nb_replicas).map(|i| t_min * ratio.powi(i as i32)).collect()
+}
+
+/// Widens the gaps between temperatures whose swaps are accepted more often than average and narrows
+/// the others, keeping both ends of the ladder in place. A ladder with no spread, e.g. the one built
+/// for a problem without couplings, is left alone.
+fn adapt_ladder(temperatures: &mut [f64], rates: &[f64]) {
+    let mean_rate = rates.iter().sum::<f64>() / rates.len() as f64;
+    let log_span = f64::ln(temperatures[temperatures.len() - 1] / temperatures[0]);
+
+    let gaps: Vec<f64> = temperatures
+        .windows(2)
+        .zip(rates)
+        .map(|(pair, rate)| f64::ln(pair[1] / pair[0]) * f64::exp(rate - mean_rate))
+        .collect();
+    let total_gap = gaps.iter().sum::<f64>();
+    if total_gap <= 0.0 {
+        return;
+    }
+    let scale = log_span / total_gap;
+
+    for (i, gap) in gaps.iter().enumerate().take(gaps.len() - 1) {
+        temperatures[i + 1] = temperatures[i] * f64::exp(gap * scale);
+    }
+}
+
+/// The lowest of the evaluations of some solutions
+pub(super) fn lowest_evaluation<T>(evaluated: &[(QuboSolution, T)]) -> T
+    where
+        T: QuboWeight,
+{
+    evaluated
+        .iter()
+        .map(|(_, evaluation)| *evaluation)
+        .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        .expect("There is at least one solution")
+}
+
+/// A random solution that is updated by Metropolis sweeps
+#[derive(Clone)]
+pub(super) struct Replica<T>(LocalFieldState<T>);
+
+impl<T> Replica<T>
+    where
+        T: QuboWeight,
+{
+    pub(super) fn new(qubo_problem: &QuboProblem<T>, rng: &mut impl Rng) -> Self {
+        let solution = QuboSolution::from_fn(qubo_problem.get_size(), |_| rng.gen_range(0..=1) == 1);
+
+        Self::from_solution(qubo_problem, solution)
+    }
+
+    pub(super) fn from_solution(qubo_problem: &QuboProblem<T>, solution: QuboSolution) -> Self {
+        Self(LocalFieldState::new(qubo_problem, solution))
+    }
+
+    pub(super) fn solution(&self) -> &QuboSolution {
+        self.0.solution()
+    }
+
+    pub(super) fn evaluation(&self) -> T {
+        self.0.evaluation()
+    }
+
+    /// Tries to flip every bit once with the Metropolis criterion, returning the best solution seen
+    pub(super) fn sweep(&mut self, qubo_problem: &QuboProblem<T>, temperature: f64, rng: &mut impl Rng) -> Option<(QuboSolution, T)> {
+        let mut best = None;
+
+        for i in 0..qubo_problem.get_size() {
+            let delta = self.0.delta(i);
+
+            if delta > T::zero() && rng.gen_range(0.0..1.0) >= f64::exp(-to_f64(delta) / temperature) {
+                continue;
+            }
+
+            self.0.flip(qubo_problem, i);
+
+            if best.as_ref().is_none_or(|(_, best_evaluation)| self.evaluation() < *best_evaluation) {
+                best = Some((self.solution().clone(), self.evaluation()));
+            }
+        }
+
+        best
+    }
+}
+
+impl<T> Solver<QuboProblem<T>> for ParallelTempering<T>
+    where
+        T: QuboWeight,
+{
+    fn sample_until(&mut self, qubo_problem: &QuboProblem<T>, criteria: &StoppingCriteria<T>) -> SampleSet<QuboSolution, T> {
+        let condition = criteria.start();
+        let seed = self.seed.unwrap_or_else(random_seed);
+        let mut rng = seeded_rng(seed, 0);
+
+        let mut temperatures = self
+            .temperatures
+            .clone()
+            .unwrap_or_else(|| default_ladder(qubo_problem, self.nb_replicas.get()));
+        let nb_replicas = temperatures.len();
+        // Each temperature sweeps with its own stream, whichever replica is at it
+        let mut sweep_rngs: Vec<StdRng> = (1..=nb_replicas as u64).map(|stream| seeded_rng(seed, stream)).collect();
+
+        let mut replicas: Vec<_> = (0..nb_replicas)
+            .map(|i| Replica::from_solution(qubo_problem, starting_solution(&self.initial_states, i, qubo_problem.get_size(), &mut rng)))
+            .collect();
+        // The best solution seen at each temperature, whichever replica was at it
+        let mut slot_bests: Vec<_> = replicas.iter().map(|replica| (replica.solution().clone(), replica.evaluation())).collect();
+        let mut best_evaluation = lowest_evaluation(&slot_bests);
+
+        let mut statistics = SwapStatistics::new(temperatures.clone());
+        let mut window = SwapStatistics::new(temperatures.clone());
+        let mut swap_round = 0;
+
+        let max_k = self.max_sweeps.get();
+        let mut sweeps = max_k;
+        for k in 0..max_k {
+            for (i, replica) in replicas.iter().enumerate() {
+                self.observers.notify_with(|| SolverEvent {
+                    iteration: k,
+                    energy: replica.evaluation(),
+                    best_energy: best_evaluation,
+                    replica: i,
+                    elapsed: condition.elapsed(),
+                });
+            }
+
+            condition.record_evaluations(nb_replicas * qubo_problem.get_size());
+            if condition.should_stop(&best_evaluation) {
+                sweeps = k;
+                break;
+            }
+
+            replicas
+                .par_iter_mut()
+                .zip(&temperatures)
+                .zip(&mut sweep_rngs)
+                .zip(&mut slot_bests)
+                .for_each(|(((replica, &temperature), rng), slot_best)| {
+                    if let Some((solution, evaluation)) = replica.sweep(qubo_problem, temperature, rng)
+                        && evaluation < slot_best.1
+                    {
+                        *slot_best = (solution, evaluation);
+                    }
+                });
+            best_evaluation = lowest_evaluation(&slot_bests);
+
+            if (k + 1) % self.swap_interval.get() != 0 || nb_replicas < 2 {
+                continue;
+            }
+
+            // Alternating between even and odd pairs lets a solution travel the whole ladder
+            for i in (swap_round % 2..nb_replicas - 1).step_by(2) {
+                let beta_difference = 1.0 / temperatures[i] - 1.0 / temperatures[i + 1];
+                let energy_difference = to_f64(replicas[i].evaluation()) - to_f64(replicas[i + 1].evaluation());
+
+                statistics.attempted[i] += 1;
+                window.attempted[i] += 1;
+                if rng.gen_range(0.0..1.0) < f64::exp(beta_difference * energy_difference) {
+                    replicas.swap(i, i + 1);
+                    statistics.accepted[i] += 1;
+                    window.accepted[i] += 1;
+                }
+            }
+            swap_round += 1;
+
+            if self.adaptive && nb_replicas > 2 && 2 * k < max_k && swap_round % ADAPTATION_INTERVAL == 0 {
+                adapt_ladder(&mut temperatures, &window.acceptance_rates());
+                window = SwapStatistics::new(temperatures.clone());
+            }
+        }
+
+        for (i, replica) in replicas.iter().enumerate() {
+            self.observers.notify_with(|| SolverEvent {
+                iteration: sweeps,
+                energy: replica.evaluation(),
+                best_energy: best_evaluation,
+                replica: i,
+                elapsed: condition.elapsed(),
+            });
+        }
+
+        statistics.temperatures = temperatures;
+        debug!(
+            "Parallel tempering ladder {:?} with swap acceptance rates {:?}",
+            statistics.temperatures,
+            statistics.acceptance_rates()
+        );
+        self.statistics = Some(statistics);
+
+        slot_bests
+            .into_iter()
+            .enumerate()
+            .map(|(slot, (solution, evaluation))| (solution, evaluation, slot))
+            .collect()
+    }
+}
@@ -1,116 +1,182 @@
-use std::cmp::{max_by_key, min_by_key};
+use std::cmp::{max_by, min_by, Ordering};
 use std::num::NonZeroUsize;
+use std::sync::Arc;
 
-use nalgebra::DVector;
 use rand::prelude::IteratorRandom;
-use rand::{Rng, thread_rng};
+use rand::Rng;
 use rayon::prelude::*;
 
-use crate::core::Solver;
-use crate::logging::log_solver_performance;
-use crate::qubo::{QuboProblem, QuboSolution, QuboType};
+use crate::core::{SampleSet, Solver, StoppingCriteria};
+use crate::logging::{Observers, SolverEvent, SolverObserver};
+use crate::qubo::{LocalFieldState, QuboProblem, QuboSolution, QuboType, QuboWeight};
+use crate::qubo::solvers::schedule::{Exponential, Schedule};
+use crate::utils::{random_seed, seeded_rng, to_f64};
 
-pub struct SimulatedAnnealer
+/// At every iteration, each replica flips a random bit among those whose delta is within a fraction
+/// of the range of deltas above the lowest one. The fraction is drawn below the temperature, which is
+/// clamped to `[0, 1]`, so that only the best flips are taken as it goes to zero.
+pub struct SimulatedAnnealer<T = QuboType>
 {
     max_iterations: NonZeroUsize,
-    parallelism: NonZeroUsize
+    parallelism: NonZeroUsize,
+    temperature: Box<dyn Schedule>,
+    initial_states: Vec<QuboSolution>,
+    seed: Option<u64>,
+    observers: Observers<T>,
 }
 
-impl SimulatedAnnealer
+impl<T> SimulatedAnnealer<T>
 {
-    pub fn new(max_iterations: NonZeroUsize, parallelism: NonZeroUsize) -> Self {
+    pub fn new(max_iterations: NonZeroUsize, parallelism: NonZeroUsize, temperature: impl Schedule + 'static) -> Self {
         Self {
             max_iterations,
-            parallelism
+            parallelism,
+            temperature: Box::new(temperature),
+            initial_states: Vec::new(),
+            seed: None,
+            observers: Observers::default(),
         }
     }
+
+    /// Starts the replicas from these solutions instead of random ones, e.g. to refine the samples of
+    /// a previous run. Replica `r` starts from solution `r mod n`.
+    pub fn with_initial_states(mut self, initial_states: impl IntoIterator<Item=QuboSolution>) -> Self {
+        self.initial_states = initial_states.into_iter().collect();
+
+        self
+    }
+
+    /// Gives every replica its own random stream of this seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+
+        self
+    }
+
+    pub fn with_observer(mut self, observer: Arc<dyn SolverObserver<T>>) -> Self {
+        self.observers.push(observer);
+
+        self
+    }
+}
+
+// The default schedules do not depend on the weights, so they are only defined once
+impl SimulatedAnnealer {
+    /// Decays from `1` to `exp(-5)` over the run
+    pub fn default_temperature(max_iterations: NonZeroUsize) -> Exponential {
+        Exponential::new(1.0, 5.0 / max_iterations.get() as f64)
+    }
 }
 
-fn temperature(x: f64) -> f64 {
-    const K: f64 = 5.0;
+/// The solution that a replica starts from: the initial states are handed out to the replicas in
+/// turn, or random bits are drawn when there are none
+pub(super) fn starting_solution(initial_states: &[QuboSolution], replica: usize, problem_size: usize, rng: &mut impl Rng) -> QuboSolution {
+    if initial_states.is_empty() {
+        return QuboSolution::from_fn(problem_size, |_| rng.gen_range(0..=1) == 1);
+    }
+
+    let solution = &initial_states[replica % initial_states.len()];
+    assert_eq!(problem_size, solution.len(), "Initial states must have the size of the problem");
 
-    f64::exp(-x * K)
+    solution.clone()
 }
 
-impl Solver<QuboProblem> for SimulatedAnnealer {
-    fn solve(&mut self, qubo_problem: &QuboProblem) -> QuboSolution {
-        (0..self.parallelism.get()).into_par_iter().map(|_| {
-            let mut rng = thread_rng();
-            let mut current_solution =
-                QuboSolution(DVector::from_fn(qubo_problem.get_size(), |_, _| {
-                    rng.gen_range(0..=1)
-                }));
-            let mut current_evaluation = qubo_problem.evaluate(&current_solution);
-            let mut current_deltas: Vec<_> = (0..qubo_problem.get_size())
-                .map(|i| qubo_problem.delta_evaluate_k(&current_solution, i))
-                .collect();
+fn compare_deltas<T>((_, a): &(usize, T), (_, b): &(usize, T)) -> Ordering
+    where
+        T: QuboWeight,
+{
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
 
-            let mut best_solution = current_solution.clone();
-            let mut best_evaluation = current_evaluation;
+impl<T> Solver<QuboProblem<T>> for SimulatedAnnealer<T>
+    where
+        T: QuboWeight,
+{
+    fn sample_until(&mut self, qubo_problem: &QuboProblem<T>, criteria: &StoppingCriteria<T>) -> SampleSet<QuboSolution, T> {
+        let condition = criteria.start();
+        let seed = self.seed.unwrap_or_else(random_seed);
+
+        (0..self.parallelism.get()).into_par_iter().map(|replica| {
+            let mut rng = seeded_rng(seed, replica as u64);
+            let mut current = LocalFieldState::new(
+                qubo_problem,
+                starting_solution(&self.initial_states, replica, qubo_problem.get_size(), &mut rng),
+            );
+
+            let mut best_solution = current.solution().clone();
+            let mut best_evaluation = current.evaluation();
 
 
             let max_k = self.max_iterations.get();
+            let mut iterations = max_k;
             for k in 0..max_k {
-                log_solver_performance(current_evaluation, k);
+                self.observers.notify_with(|| SolverEvent {
+                    iteration: k,
+                    energy: current.evaluation(),
+                    best_energy: best_evaluation,
+                    replica,
+                    elapsed: condition.elapsed(),
+                });
+
+                condition.record_evaluations(1);
+                if condition.should_stop(&best_evaluation) {
+                    iterations = k;
+                    break;
+                }
                 
-                let t = temperature((k + 1) as f64 / (max_k as f64));
+                let t = self.temperature.value(k).clamp(0.0, 1.0);
 
-                let ((min_delta_i, min_delta), (_, max_delta)) = current_deltas
+                let ((min_delta_i, min_delta), (_, max_delta)) = current
+                    .deltas()
                     .iter()
                     .cloned()
                     .enumerate()
                     .fold(None, |m, x| {
                         m.map_or(Some((x, x)), |(m1, m2)| {
                             Some((
-                                min_by_key(m1, x, |(_, v)| *v),
-                                max_by_key(m2, x, |(_, v)| *v),
+                                min_by(m1, x, compare_deltas),
+                                max_by(m2, x, compare_deltas),
                             ))
                         })
                     })
                     .expect("Deltas cannot be empty!");
-                let p = rng.gen_range(0f64..t);
+                let p = if t > 0.0 { rng.gen_range(0f64..t) } else { 0.0 };
                 let max_acceptable_value =
-                    ((1.0 - p) * min_delta as f64 + p * max_delta as f64).ceil() as QuboType;
+                    (1.0 - p) * to_f64(min_delta) + p * to_f64(max_delta);
 
-                let valid_choices = current_deltas
+                let valid_choices = current
+                    .deltas()
                     .iter()
                     .cloned()
                     .enumerate()
-                    .filter(|(_, x)| *x <= max_acceptable_value)
+                    .filter(|(_, x)| to_f64(*x) <= max_acceptable_value)
                     .map(|(i, _)| i);
 
                 let random_i = valid_choices
                     .choose(&mut rng)
                     .expect("Choices cannot be empty (It must at least have min)");
 
-                let random_evaluation = current_evaluation + current_deltas[random_i];
-
-                let min_evaluation = current_evaluation + min_delta;
+                let min_evaluation = current.evaluation() + min_delta;
                 if min_evaluation < best_evaluation {
-                    best_solution = current_solution.flip(min_delta_i);
+                    best_solution = current.solution().flip(min_delta_i);
                     best_evaluation = min_evaluation;
                 }
 
-                // Calculating deltas must be done before updating the solution!
-                current_deltas = current_deltas
-                    .into_iter()
-                    .enumerate()
-                    .map(|(j, d_j)| {
-                        qubo_problem.flip_j_and_delta_evaluate_k(&current_solution, d_j, random_i, j)
-                    })
-                    .collect();
-
-                current_evaluation = random_evaluation;
-                current_solution = current_solution.flip(random_i);
-                
+                current.flip(qubo_problem, random_i);
             }
-            
-            log_solver_performance(current_evaluation, max_k);
 
-            (best_solution, best_evaluation)
+            self.observers.notify_with(|| SolverEvent {
+                iteration: iterations,
+                energy: current.evaluation(),
+                best_energy: best_evaluation,
+                replica,
+                elapsed: condition.elapsed(),
+            });
+
+            (best_solution, best_evaluation, replica)
         })
-            .min_by_key(|(_,x)| *x)
-            .expect("Parallelism cannot be zero!")
-            .0
+            .collect::<Vec<_>>()
+            .into_iter()
+            .collect()
     }
 }
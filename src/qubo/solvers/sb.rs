@@ -0,0 +1,235 @@
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use log::debug;
+use nalgebra::DVector;
+use nalgebra_sparse::CsrMatrix;
+use rand::Rng;
+use rayon::prelude::*;
+
+use crate::core::{Reduction, SampleSet, Solver, StoppingCondition, StoppingCriteria};
+use crate::ising::{IsingProblem, IsingSolution};
+use crate::ising::reductions::QuboToIsing;
+use crate::logging::{Observers, SolverEvent, SolverObserver};
+use crate::qubo::{QuboProblem, QuboSolution, QuboType, QuboWeight};
+use crate::utils::{random_seed, seeded_rng, to_f64};
+
+type SbType = f64;
+
+/// How the couplings act on the oscillators
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BifurcationVariant {
+    /// Ballistic SB, where each oscillator feels the positions of the others
+    Ballistic,
+    /// Discrete SB, where each oscillator only feels the signs of the positions of the others
+    Discrete,
+}
+
+/// Simulated bifurcation (Goto et al., 2019 and 2021). Every spin is an oscillator with a position
+/// `x_i` and a momentum `y_i`, integrated with the symplectic Euler method:
+///
+/// `y_i += dt (-(a_0 - a(t)) x_i - c_0 (sum_j J_ij x_j + h_i))`, `x_i += dt a_0 y_i`
+///
+/// As the pump `a(t)` rises from `0` to `a_0`, every oscillator bifurcates towards `-1` or `1`,
+/// where inelastic walls stop it. The spins are the signs of the positions. Each of the
+/// `parallelism` trajectories starts from its own random positions.
+pub struct SimulatedBifurcation<T = QuboType>
+{
+    max_steps: NonZeroUsize,
+    parallelism: NonZeroUsize,
+    variant: BifurcationVariant,
+    time_step: SbType,
+    pump: Box<dyn Fn(SbType) -> SbType + Send + Sync>,
+    coupling: Option<SbType>,
+    seed: Option<u64>,
+    observers: Observers<T>,
+}
+
+impl<T> SimulatedBifurcation<T>
+{
+    pub fn new(max_steps: NonZeroUsize, parallelism: NonZeroUsize) -> Self {
+        Self {
+            max_steps,
+            parallelism,
+            variant: BifurcationVariant::Ballistic,
+            time_step: 1.25,
+            pump: Box::new(|x| x),
+            coupling: None,
+            seed: None,
+            observers: Observers::default(),
+        }
+    }
+
+    /// Defaults to [BifurcationVariant::Ballistic]
+    pub fn with_variant(mut self, variant: BifurcationVariant) -> Self {
+        self.variant = variant;
+
+        self
+    }
+
+    /// The integration time step `dt`. Defaults to `1.25`.
+    pub fn with_time_step(mut self, time_step: SbType) -> Self {
+        self.time_step = time_step;
+
+        self
+    }
+
+    /// The pump `a(t) / a_0` as a function of the progress of the run, from `0` at the first step to
+    /// `1` at the last. Defaults to a linear ramp.
+    pub fn with_pump_schedule(mut self, pump: impl Fn(SbType) -> SbType + Send + Sync + 'static) -> Self {
+        self.pump = Box::new(pump);
+
+        self
+    }
+
+    /// The strength `c_0` of the couplings. Defaults to `0.5 / (σ_J sqrt(n))`, where `σ_J` is the
+    /// root mean square of the couplings and biases.
+    pub fn with_coupling(mut self, coupling: SbType) -> Self {
+        self.coupling = Some(coupling);
+
+        self
+    }
+
+    /// Gives every trajectory its own random stream of this seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+
+        self
+    }
+
+    pub fn with_observer(mut self, observer: Arc<dyn SolverObserver<T>>) -> Self {
+        self.observers.push(observer);
+
+        self
+    }
+}
+
+impl<T> SimulatedBifurcation<T>
+    where
+        T: QuboWeight,
+{
+    /// Integrates the trajectories, returning the best spins of each according to `evaluate`. This is
+    /// the energy of the problem being solved, so that observers and stopping criteria use its units.
+    fn bifurcate(
+        &self,
+        ising_problem: &IsingProblem<T>,
+        condition: &StoppingCondition<T>,
+        evaluate: impl Fn(&IsingSolution) -> T + Sync,
+    ) -> SampleSet<IsingSolution, T> {
+        const A_0: SbType = 1.0;
+
+        let problem_size = ising_problem.get_size();
+        let couplings = ising_problem.get_couplings();
+        let j_mat = CsrMatrix::try_from_pattern_and_values(
+            couplings.pattern().clone(),
+            couplings.values().iter().map(|v| to_f64(*v)).collect(),
+        )
+            .expect("The pattern is unchanged");
+        let h_bias: DVector<SbType> = ising_problem.get_biases().map(to_f64);
+
+        let c_0 = self.coupling.unwrap_or_else(|| {
+            let nb_terms = j_mat.nnz() + problem_size;
+            let sum_of_squares = j_mat.values().iter().chain(h_bias.iter()).map(|v| v * v).sum::<SbType>();
+            let rms = if sum_of_squares > 0.0 { SbType::sqrt(sum_of_squares / nb_terms as SbType) } else { 1.0 };
+
+            0.5 / (rms * SbType::sqrt(problem_size.max(1) as SbType))
+        });
+        debug!("Using coupling strength {c_0}");
+
+        let to_solution = |x: &DVector<SbType>| IsingSolution(x.map(|x_i| if x_i >= 0.0 { 1 } else { -1 }));
+        let dt = self.time_step;
+        let max_k = self.max_steps.get();
+        let seed = self.seed.unwrap_or_else(random_seed);
+
+        (0..self.parallelism.get()).into_par_iter().map(|replica| {
+            let mut rng = seeded_rng(seed, replica as u64);
+            let mut x: DVector<SbType> = DVector::from_fn(problem_size, |_, _| rng.gen_range(-0.1..0.1));
+            let mut y: DVector<SbType> = DVector::from_fn(problem_size, |_, _| rng.gen_range(-0.1..0.1));
+
+            let mut current_solution = to_solution(&x);
+            let mut current_evaluation = evaluate(&current_solution);
+            let mut best_solution = current_solution.clone();
+            let mut best_evaluation = current_evaluation;
+
+            let mut steps = max_k;
+            for k in 0..max_k {
+                self.observers.notify_with(|| SolverEvent {
+                    iteration: k,
+                    energy: current_evaluation,
+                    best_energy: best_evaluation,
+                    replica,
+                    elapsed: condition.elapsed(),
+                });
+
+                condition.record_evaluations(1);
+                if condition.should_stop(&best_evaluation) {
+                    steps = k;
+                    break;
+                }
+
+                let a_t = A_0 * (self.pump)(if max_k > 1 { k as SbType / (max_k - 1) as SbType } else { 1.0 });
+
+                let field = match self.variant {
+                    BifurcationVariant::Ballistic => &j_mat * &x,
+                    BifurcationVariant::Discrete => &j_mat * x.map(|x_i| if x_i >= 0.0 { 1.0 } else { -1.0 }),
+                } + &h_bias;
+
+                y += (x.scale(-(A_0 - a_t)) - field.scale(c_0)).scale(dt);
+                x += y.scale(dt * A_0);
+
+                // Inelastic walls at -1 and 1
+                for (x_i, y_i) in x.iter_mut().zip(y.iter_mut()) {
+                    if x_i.abs() > 1.0 {
+                        *x_i = x_i.signum();
+                        *y_i = 0.0;
+                    }
+                }
+
+                current_solution = to_solution(&x);
+                current_evaluation = evaluate(&current_solution);
+                if current_evaluation < best_evaluation {
+                    best_solution = current_solution.clone();
+                    best_evaluation = current_evaluation;
+                }
+            }
+
+            self.observers.notify_with(|| SolverEvent {
+                iteration: steps,
+                energy: current_evaluation,
+                best_energy: best_evaluation,
+                replica,
+                elapsed: condition.elapsed(),
+            });
+
+            (best_solution, best_evaluation, replica)
+        })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+impl<T> Solver<IsingProblem<T>> for SimulatedBifurcation<T>
+    where
+        T: QuboWeight,
+{
+    fn sample_until(&mut self, ising_problem: &IsingProblem<T>, criteria: &StoppingCriteria<T>) -> SampleSet<IsingSolution, T> {
+        let condition = criteria.start();
+
+        self.bifurcate(ising_problem, &condition, |solution| ising_problem.evaluate(solution))
+    }
+}
+
+impl<T> Solver<QuboProblem<T>> for SimulatedBifurcation<T>
+    where
+        T: QuboWeight,
+{
+    fn sample_until(&mut self, qubo_problem: &QuboProblem<T>, criteria: &StoppingCriteria<T>) -> SampleSet<QuboSolution, T> {
+        let condition = criteria.start();
+        let (ising_problem, _) = QuboToIsing::reduce(qubo_problem);
+
+        // The trajectories are already evaluated on the QUBO, so only the solutions are mapped
+        self.bifurcate(&ising_problem, &condition, |solution| qubo_problem.evaluate(&solution.clone().into()))
+            .map(|solution, evaluation| (solution.into(), evaluation))
+    }
+}
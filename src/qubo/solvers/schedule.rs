@@ -0,0 +1,136 @@
+/// A parameter of a solver that changes over its iterations, such as the temperature of an annealer.
+///
+/// Any `Fn(usize) -> f64` is a schedule, which allows for custom ones.
+pub trait Schedule: Send + Sync {
+    /// The value of the parameter at iteration `step`
+    fn value(&self, step: usize) -> f64;
+}
+
+impl<F> Schedule for F
+    where
+        F: Fn(usize) -> f64 + Send + Sync,
+{
+    fn value(&self, step: usize) -> f64 {
+        self(step)
+    }
+}
+
+/// Moves linearly from `start` to `end` over `nb_steps` steps, and then stays at `end`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Linear {
+    start: f64,
+    end: f64,
+    nb_steps: usize,
+}
+
+impl Linear {
+    pub fn new(start: f64, end: f64, nb_steps: usize) -> Self {
+        Self { start, end, nb_steps }
+    }
+}
+
+impl Schedule for Linear {
+    fn value(&self, step: usize) -> f64 {
+        if step >= self.nb_steps {
+            return self.end;
+        }
+
+        self.start + (self.end - self.start) * step as f64 / self.nb_steps as f64
+    }
+}
+
+/// Moves from `start` to `end` over `nb_steps` steps by multiplying by the same ratio at every step,
+/// and then stays at `end`. Both ends must be positive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Geometric {
+    start: f64,
+    end: f64,
+    nb_steps: usize,
+}
+
+impl Geometric {
+    pub fn new(start: f64, end: f64, nb_steps: usize) -> Self {
+        assert!(start > 0.0 && end > 0.0, "A geometric schedule needs positive ends");
+
+        Self { start, end, nb_steps }
+    }
+}
+
+impl Schedule for Geometric {
+    fn value(&self, step: usize) -> f64 {
+        if step >= self.nb_steps {
+            return self.end;
+        }
+
+        self.start * f64::powf(self.end / self.start, step as f64 / self.nb_steps as f64)
+    }
+}
+
+/// `start exp(-rate step)`, which decays without ever reaching zero
+#[derive(Clone, Debug, PartialEq)]
+pub struct Exponential {
+    start: f64,
+    rate: f64,
+}
+
+impl Exponential {
+    pub fn new(start: f64, rate: f64) -> Self {
+        Self { start, rate }
+    }
+}
+
+impl Schedule for Exponential {
+    fn value(&self, step: usize) -> f64 {
+        self.start * f64::exp(-self.rate * step as f64)
+    }
+}
+
+/// `scale / ln(1 + step)`, the slow cooling under which annealing provably converges (Geman and
+/// Geman, 1984). It is infinite at the first step.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Logarithmic {
+    scale: f64,
+}
+
+impl Logarithmic {
+    pub fn new(scale: f64) -> Self {
+        Self { scale }
+    }
+}
+
+impl Schedule for Logarithmic {
+    fn value(&self, step: usize) -> f64 {
+        self.scale / f64::ln_1p(step as f64)
+    }
+}
+
+/// Interpolates linearly between `(step, value)` points, and stays at the first and last values
+/// before and after them
+#[derive(Clone, Debug, PartialEq)]
+pub struct Piecewise {
+    points: Vec<(usize, f64)>,
+}
+
+impl Piecewise {
+    pub fn new(points: Vec<(usize, f64)>) -> Self {
+        assert!(!points.is_empty(), "A piecewise schedule needs at least one point");
+        assert!(points.windows(2).all(|pair| pair[0].0 < pair[1].0), "Points must be sorted by step");
+
+        Self { points }
+    }
+}
+
+impl Schedule for Piecewise {
+    fn value(&self, step: usize) -> f64 {
+        let next = self.points.partition_point(|(point_step, _)| *point_step <= step);
+
+        match (self.points.get(next.wrapping_sub(1)), self.points.get(next)) {
+            (Some(&(a_step, a)), Some(&(b_step, b))) => {
+                a + (b - a) * (step - a_step) as f64 / (b_step - a_step) as f64
+            }
+            (Some(&(_, a)), None) => a,
+            (None, Some(&(_, b))) => b,
+            (None, None) => unreachable!("There is at least one point"),
+        }
+    }
+}
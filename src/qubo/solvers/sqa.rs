@@ -0,0 +1,272 @@
+use std::cmp::Ordering;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use log::debug;
+use nalgebra::DVector;
+use rand::Rng;
+use rand::rngs::StdRng;
+use rayon::prelude::*;
+
+use crate::core::{Reduction, SampleSet, Solver, StoppingCondition, StoppingCriteria};
+use crate::ising::{IsingProblem, IsingSolution};
+use crate::ising::reductions::QuboToIsing;
+use crate::logging::{Observers, SolverEvent, SolverObserver};
+use crate::qubo::{QuboProblem, QuboSolution, QuboType, QuboWeight};
+use crate::utils::{random_seed, seeded_rng, to_f64};
+
+/// Simulated quantum annealing by path-integral Monte Carlo. The transverse field Ising model is
+/// mapped onto `nb_slices` classical copies of the problem, the Trotter slices, arranged in a ring
+/// where each spin is coupled ferromagnetically to its copies in the neighbouring slices. The
+/// coupling `J_perp = -(P T / 2) ln tanh(Γ / (P T))` grows as the transverse field `Γ` is lowered,
+/// until every slice agrees on a classical solution.
+///
+/// The transverse field and the temperature are interpolated linearly between their start and end
+/// values, in units of the Ising energy. Unless they are given, they are derived from the mean
+/// magnitude of the coefficients of the problem.
+pub struct SimulatedQuantumAnnealer<T = QuboType>
+{
+    max_sweeps: NonZeroUsize,
+    nb_slices: NonZeroUsize,
+    transverse_field: Option<(f64, f64)>,
+    temperature: Option<(f64, f64)>,
+    seed: Option<u64>,
+    observers: Observers<T>,
+}
+
+impl<T> SimulatedQuantumAnnealer<T>
+{
+    pub fn new(max_sweeps: NonZeroUsize, nb_slices: NonZeroUsize) -> Self {
+        Self {
+            max_sweeps,
+            nb_slices,
+            transverse_field: None,
+            temperature: None,
+            seed: None,
+            observers: Observers::default(),
+        }
+    }
+
+    /// The transverse field at the first and last sweeps
+    pub fn with_transverse_field(mut self, start: f64, end: f64) -> Self {
+        assert!(start > 0.0 && end > 0.0, "The transverse field must be positive");
+
+        self.transverse_field = Some((start, end));
+
+        self
+    }
+
+    /// The temperature at the first and last sweeps
+    pub fn with_temperature(mut self, start: f64, end: f64) -> Self {
+        assert!(start > 0.0 && end > 0.0, "The temperature must be positive");
+
+        self.temperature = Some((start, end));
+
+        self
+    }
+
+    /// Gives every slice its own random stream of this seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+
+        self
+    }
+
+    pub fn with_observer(mut self, observer: Arc<dyn SolverObserver<T>>) -> Self {
+        self.observers.push(observer);
+
+        self
+    }
+}
+
+fn interpolate((start, end): (f64, f64), x: f64) -> f64 {
+    start + (end - start) * x
+}
+
+/// The mean magnitude of the non zero couplings and biases, used as the unit of energy
+fn energy_unit<T>(ising_problem: &IsingProblem<T>) -> f64
+    where
+        T: QuboWeight,
+{
+    let magnitudes: Vec<f64> = ising_problem
+        .get_couplings()
+        .values()
+        .iter()
+        .chain(ising_problem.get_biases().iter())
+        .map(|v| to_f64(*v).abs())
+        .filter(|v| *v > 0.0)
+        .collect();
+
+    if magnitudes.is_empty() { 1.0 } else { magnitudes.iter().sum::<f64>() / magnitudes.len() as f64 }
+}
+
+/// Slices updated together never neighbour each other. An odd ring needs a third phase for its last
+/// slice, which neighbours both slice `0` and an even slice.
+fn phase(slice: usize, nb_slices: usize) -> usize {
+    if nb_slices % 2 == 1 && slice == nb_slices - 1 && nb_slices > 1 { 2 } else { slice % 2 }
+}
+
+/// Tries to flip every spin of a slice once with the Metropolis criterion at the temperature `P T`
+fn sweep<T>(
+    ising_problem: &IsingProblem<T>,
+    mut slice: IsingSolution,
+    neighbours: (&IsingSolution, &IsingSolution),
+    slice_coupling: f64,
+    slice_temperature: f64,
+    rng: &mut impl Rng,
+) -> IsingSolution
+    where
+        T: QuboWeight,
+{
+    for i in 0..ising_problem.get_size() {
+        let neighbour_spins = f64::from(neighbours.0.0[i]) + f64::from(neighbours.1.0[i]);
+        let delta = to_f64(ising_problem.delta_evaluate_k(&slice, i))
+            + 2.0 * slice_coupling * f64::from(slice.0[i]) * neighbour_spins;
+
+        if delta <= 0.0 || rng.gen_range(0.0..1.0) < f64::exp(-delta / slice_temperature) {
+            slice.0[i] = -slice.0[i];
+        }
+    }
+
+    slice
+}
+
+impl<T> SimulatedQuantumAnnealer<T>
+    where
+        T: QuboWeight,
+{
+    /// Anneals the slices, returning the best classical solution of each according to `evaluate`. This
+    /// is the energy of the problem being solved, so that observers and stopping criteria use its units.
+    fn anneal(
+        &self,
+        ising_problem: &IsingProblem<T>,
+        condition: &StoppingCondition<T>,
+        evaluate: impl Fn(&IsingSolution) -> T + Sync,
+    ) -> SampleSet<IsingSolution, T> {
+        let problem_size = ising_problem.get_size();
+        let nb_slices = self.nb_slices.get();
+
+        let unit = energy_unit(ising_problem);
+        let transverse_field = self.transverse_field.unwrap_or((3.0 * unit, 1e-3 * unit));
+        let temperature = self.temperature.unwrap_or((0.05 * unit, 0.05 * unit));
+
+        let seed = self.seed.unwrap_or_else(random_seed);
+        let mut rng = seeded_rng(seed, 0);
+        let mut sweep_rngs: Vec<StdRng> = (1..=nb_slices as u64).map(|stream| seeded_rng(seed, stream)).collect();
+        let mut slices: Vec<_> = (0..nb_slices)
+            .map(|_| IsingSolution(DVector::from_fn(problem_size, |_, _| if rng.gen_bool(0.5) { 1 } else { -1 })))
+            .collect();
+
+        let evaluations = |slices: &[IsingSolution]| -> Vec<T> { slices.par_iter().map(&evaluate).collect() };
+
+        let mut current_evaluations = evaluations(&slices);
+        let mut slice_bests: Vec<_> = slices.iter().cloned().zip(current_evaluations.iter().cloned()).collect();
+        let lowest_evaluation = |slice_bests: &[(IsingSolution, T)]| {
+            slice_bests
+                .iter()
+                .map(|(_, evaluation)| *evaluation)
+                .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                .expect("There is at least one slice!")
+        };
+        let mut best_evaluation = lowest_evaluation(&slice_bests);
+
+        let max_k = self.max_sweeps.get();
+        let mut sweeps = max_k;
+        for k in 0..max_k {
+            for (slice, evaluation) in current_evaluations.iter().enumerate() {
+                self.observers.notify_with(|| SolverEvent {
+                    iteration: k,
+                    energy: *evaluation,
+                    best_energy: best_evaluation,
+                    replica: slice,
+                    elapsed: condition.elapsed(),
+                });
+            }
+
+            condition.record_evaluations(nb_slices * problem_size);
+            if condition.should_stop(&best_evaluation) {
+                sweeps = k;
+                break;
+            }
+
+            let x = if max_k > 1 { k as f64 / (max_k - 1) as f64 } else { 1.0 };
+            let gamma = interpolate(transverse_field, x);
+            let slice_temperature = nb_slices as f64 * interpolate(temperature, x);
+            // A single slice is the classical problem, without any transverse field
+            let slice_coupling = if nb_slices > 1 {
+                -slice_temperature / 2.0 * f64::ln(f64::tanh(gamma / slice_temperature))
+            } else {
+                0.0
+            };
+
+            for current_phase in 0..3 {
+                let updated: Vec<_> = sweep_rngs
+                    .par_iter_mut()
+                    .enumerate()
+                    .filter(|(slice, _)| phase(*slice, nb_slices) == current_phase)
+                    .map(|(slice, rng)| {
+                        let neighbours = (&slices[(slice + nb_slices - 1) % nb_slices], &slices[(slice + 1) % nb_slices]);
+
+                        (slice, sweep(ising_problem, slices[slice].clone(), neighbours, slice_coupling, slice_temperature, rng))
+                    })
+                    .collect();
+
+                for (slice, solution) in updated {
+                    slices[slice] = solution;
+                }
+            }
+
+            current_evaluations = evaluations(&slices);
+            for ((slice, evaluation), slice_best) in slices.iter().zip(&current_evaluations).zip(&mut slice_bests) {
+                if *evaluation < slice_best.1 {
+                    *slice_best = (slice.clone(), *evaluation);
+                }
+            }
+            best_evaluation = lowest_evaluation(&slice_bests);
+        }
+
+        for (slice, evaluation) in current_evaluations.iter().enumerate() {
+            self.observers.notify_with(|| SolverEvent {
+                iteration: sweeps,
+                energy: *evaluation,
+                best_energy: best_evaluation,
+                replica: slice,
+                elapsed: condition.elapsed(),
+            });
+        }
+
+        let samples: SampleSet<_, _> = slice_bests
+            .into_iter()
+            .enumerate()
+            .map(|(slice, (solution, evaluation))| (solution, evaluation, slice))
+            .collect();
+        debug!("Final Evaluation is {} across {} distinct slices", best_evaluation, samples.len());
+
+        samples
+    }
+}
+
+impl<T> Solver<IsingProblem<T>> for SimulatedQuantumAnnealer<T>
+    where
+        T: QuboWeight,
+{
+    fn sample_until(&mut self, ising_problem: &IsingProblem<T>, criteria: &StoppingCriteria<T>) -> SampleSet<IsingSolution, T> {
+        let condition = criteria.start();
+
+        self.anneal(ising_problem, &condition, |solution| ising_problem.evaluate(solution))
+    }
+}
+
+impl<T> Solver<QuboProblem<T>> for SimulatedQuantumAnnealer<T>
+    where
+        T: QuboWeight,
+{
+    fn sample_until(&mut self, qubo_problem: &QuboProblem<T>, criteria: &StoppingCriteria<T>) -> SampleSet<QuboSolution, T> {
+        let condition = criteria.start();
+        let (ising_problem, _) = QuboToIsing::reduce(qubo_problem);
+
+        // The slices are already evaluated on the QUBO, so only the solutions are mapped
+        self.anneal(&ising_problem, &condition, |solution| qubo_problem.evaluate(&solution.clone().into()))
+            .map(|solution, evaluation| (solution.into(), evaluation))
+    }
+}
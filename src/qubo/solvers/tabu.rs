@@ -0,0 +1,222 @@
+use std::cmp::Ordering;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use rand::Rng;
+use rayon::prelude::*;
+
+use crate::core::{SampleSet, Solver, StoppingCondition, StoppingCriteria};
+use crate::logging::{Observers, SolverEvent, SolverObserver};
+use crate::qubo::{LocalFieldState, QuboProblem, QuboSolution, QuboType, QuboWeight};
+use crate::qubo::solvers::sa::starting_solution;
+use crate::utils::{random_seed, seeded_rng};
+
+/// Tabu search over single bit flips. Every iteration takes the best flip that is not tabu, even if
+/// it makes the solution worse, and then forbids flipping that bit back for `tenure` iterations.
+/// Each of the `parallelism` replicas starts from its own random solution.
+pub struct TabuSearch<T = QuboType>
+{
+    max_iterations: NonZeroUsize,
+    parallelism: NonZeroUsize,
+    tenure: Option<usize>,
+    aspiration: bool,
+    restart_after: Option<NonZeroUsize>,
+    initial_states: Vec<QuboSolution>,
+    seed: Option<u64>,
+    observers: Observers<T>,
+}
+
+impl<T> TabuSearch<T>
+{
+    pub fn new(max_iterations: NonZeroUsize, parallelism: NonZeroUsize) -> Self {
+        Self {
+            max_iterations,
+            parallelism,
+            tenure: None,
+            aspiration: true,
+            restart_after: None,
+            initial_states: Vec::new(),
+            seed: None,
+            observers: Observers::default(),
+        }
+    }
+
+    /// The number of iterations a flipped bit stays tabu. Defaults to a quarter of the problem size,
+    /// up to 20. It is always kept below the problem size, so that some flip is allowed.
+    pub fn with_tenure(mut self, tenure: usize) -> Self {
+        self.tenure = Some(tenure);
+
+        self
+    }
+
+    /// Whether a tabu flip is allowed when it leads to a better solution than any the replica has
+    /// found so far. Enabled by default.
+    pub fn with_aspiration(mut self, aspiration: bool) -> Self {
+        self.aspiration = aspiration;
+
+        self
+    }
+
+    /// Restarts a replica from a new random solution after this many iterations without improving
+    /// its best solution. Replicas never restart by default.
+    pub fn with_restarts(mut self, restart_after: NonZeroUsize) -> Self {
+        self.restart_after = Some(restart_after);
+
+        self
+    }
+
+    /// Starts the replicas from these solutions instead of random ones, e.g. to refine the samples of
+    /// a previous run. Replica `r` starts from solution `r mod n`, and restarts are still random.
+    pub fn with_initial_states(mut self, initial_states: impl IntoIterator<Item=QuboSolution>) -> Self {
+        self.initial_states = initial_states.into_iter().collect();
+
+        self
+    }
+
+    /// Gives every replica its own random stream of this seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+
+        self
+    }
+
+    pub fn with_observer(mut self, observer: Arc<dyn SolverObserver<T>>) -> Self {
+        self.observers.push(observer);
+
+        self
+    }
+}
+
+fn random_start<T>(qubo_problem: &QuboProblem<T>, rng: &mut impl Rng) -> LocalFieldState<T>
+    where
+        T: QuboWeight,
+{
+    let solution = QuboSolution::from_fn(qubo_problem.get_size(), |_| rng.gen_range(0..=1) == 1);
+
+    LocalFieldState::new(qubo_problem, solution)
+}
+
+impl<T> TabuSearch<T>
+    where
+        T: QuboWeight,
+{
+    /// Runs the replicas under a stopping condition that may already be shared with another solver,
+    /// so that both draw from the same budget
+    pub(super) fn search(&self, qubo_problem: &QuboProblem<T>, condition: &StoppingCondition<T>) -> SampleSet<QuboSolution, T> {
+        let problem_size = qubo_problem.get_size();
+        if problem_size == 0 {
+            return SampleSet::from_iter([(QuboSolution::zeros(0), T::zero(), 0)]);
+        }
+
+        let tenure = self.tenure.unwrap_or((problem_size / 4).clamp(1, 20)).min(problem_size - 1);
+        let seed = self.seed.unwrap_or_else(random_seed);
+
+        (0..self.parallelism.get()).into_par_iter().map(|replica| {
+            let mut rng = seeded_rng(seed, replica as u64);
+            let mut current = LocalFieldState::new(
+                qubo_problem,
+                starting_solution(&self.initial_states, replica, problem_size, &mut rng),
+            );
+
+            let mut best_solution = current.solution().clone();
+            let mut best_evaluation = current.evaluation();
+
+            // The iteration at which each bit can be flipped again
+            let mut tabu_until = vec![0; problem_size];
+            let mut last_improvement = 0;
+
+            let max_k = self.max_iterations.get();
+            let mut iterations = max_k;
+            for k in 0..max_k {
+                self.observers.notify_with(|| SolverEvent {
+                    iteration: k,
+                    energy: current.evaluation(),
+                    best_energy: best_evaluation,
+                    replica,
+                    elapsed: condition.elapsed(),
+                });
+
+                condition.record_evaluations(1);
+                if condition.should_stop(&best_evaluation) {
+                    iterations = k;
+                    break;
+                }
+
+                if self.restart_after.is_some_and(|restart_after| k - last_improvement >= restart_after.get()) {
+                    current = random_start(qubo_problem, &mut rng);
+                    tabu_until.fill(0);
+                    last_improvement = k;
+
+                    if current.evaluation() < best_evaluation {
+                        best_solution = current.solution().clone();
+                        best_evaluation = current.evaluation();
+                    }
+
+                    continue;
+                }
+
+                let is_allowed = |i: usize| {
+                    tabu_until[i] <= k || (self.aspiration && current.evaluation() + current.delta(i) < best_evaluation)
+                };
+
+                // Ties between the best flips are broken uniformly at random
+                let mut chosen: Option<(usize, T)> = None;
+                let mut nb_ties = 0;
+                for (i, delta) in current.deltas().iter().cloned().enumerate().filter(|(i, _)| is_allowed(*i)) {
+                    match chosen.map(|(_, best_delta)| delta.partial_cmp(&best_delta).unwrap_or(Ordering::Equal)) {
+                        None | Some(Ordering::Less) => {
+                            chosen = Some((i, delta));
+                            nb_ties = 1;
+                        }
+                        Some(Ordering::Equal) => {
+                            nb_ties += 1;
+                            if rng.gen_range(0..nb_ties) == 0 {
+                                chosen = Some((i, delta));
+                            }
+                        }
+                        Some(Ordering::Greater) => {}
+                    }
+                }
+
+                // The tenure is below the problem size, so this only guards against every bit being tabu
+                let flipped_i = chosen.map_or_else(
+                    || (0..problem_size).min_by_key(|&i| tabu_until[i]).expect("Problem cannot be empty!"),
+                    |(i, _)| i,
+                );
+
+                current.flip(qubo_problem, flipped_i);
+                tabu_until[flipped_i] = k + tenure + 1;
+
+                if current.evaluation() < best_evaluation {
+                    best_solution = current.solution().clone();
+                    best_evaluation = current.evaluation();
+                    last_improvement = k;
+                }
+            }
+
+            self.observers.notify_with(|| SolverEvent {
+                iteration: iterations,
+                energy: current.evaluation(),
+                best_energy: best_evaluation,
+                replica,
+                elapsed: condition.elapsed(),
+            });
+
+            (best_solution, best_evaluation, replica)
+        })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+impl<T> Solver<QuboProblem<T>> for TabuSearch<T>
+    where
+        T: QuboWeight,
+{
+    fn sample_until(&mut self, qubo_problem: &QuboProblem<T>, criteria: &StoppingCriteria<T>) -> SampleSet<QuboSolution, T> {
+        let condition = criteria.start();
+
+        self.search(qubo_problem, &condition)
+    }
+}
@@ -0,0 +1,652 @@
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use nalgebra::{DMatrix, DVector};
+use nalgebra_sparse::CsrMatrix;
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom, thread_rng};
+
+use crate::core::{CancellationToken, SampleSet, Solver, StoppingCriteria};
+use crate::logging::{JsonLinesObserver, MemoryObserver, SolverEvent, SolverObserver};
+use crate::qubo::{QuboProblem, QuboSolution, QuboType};
+
+use super::{BifurcationVariant, BranchAndBound, ExhaustiveSearch, Exponential, Geometric, GrayCodeSearch, Linear, Logarithmic, MomentumAnnealer, Mopso, ParallelExhaustiveSearch, Piecewise, PopulationAnnealer, Schedule, ParallelTempering, SimulatedAnnealer, SimulatedBifurcation, SimulatedQuantumAnnealer, TabuSearch, VariableOrder};
+
+fn random_problem(problem_size: usize) -> QuboProblem {
+    random_problem_from(problem_size, &mut thread_rng())
+}
+
+/// A fixed random problem, for the tests that expect a stochastic solver to find its optimum
+fn seeded_problem(problem_size: usize, seed: u64) -> QuboProblem {
+    random_problem_from(problem_size, &mut StdRng::seed_from_u64(seed))
+}
+
+fn random_problem_from(problem_size: usize, rng: &mut impl Rng) -> QuboProblem {
+    let q_matrix: CsrMatrix<QuboType> = CsrMatrix::from(&DMatrix::from_fn(problem_size, problem_size, |i, j| {
+        if i <= j {
+            rng.gen_range(-16..16)
+        } else {
+            0
+        }
+    }));
+
+    QuboProblem::try_from_q_matrix(q_matrix).expect("Matrix is supposedly valid")
+}
+
+#[test]
+fn check_mopso() {
+    const PROBLEM_SIZE: usize = 8;
+    const SEED: u64 = 1;
+
+    let sut_problem = seeded_problem(PROBLEM_SIZE, SEED);
+
+    let optimal = sut_problem.evaluate(&ExhaustiveSearch::new().solve(&sut_problem));
+
+    let mut sut = Mopso::new(NonZeroUsize::new(32).unwrap(), 0.7, NonZeroUsize::new(200).unwrap()).with_seed(SEED);
+    let solution = sut.solve(&sut_problem);
+
+    assert_eq!(optimal, sut_problem.evaluate(&solution));
+}
+
+#[test]
+fn check_target_energy() {
+    const PROBLEM_SIZE: usize = 16;
+
+    let sut_problem = random_problem(PROBLEM_SIZE);
+
+    let optimal = sut_problem.evaluate(&ExhaustiveSearch::new().solve(&sut_problem));
+    let criteria = StoppingCriteria::new().with_target_energy(optimal);
+
+    let solution = ParallelExhaustiveSearch::new(NonZeroUsize::new(4).unwrap()).solve_until(&sut_problem, &criteria);
+
+    assert_eq!(optimal, sut_problem.evaluate(&solution));
+}
+
+#[test]
+fn check_cancellation() {
+    const PROBLEM_SIZE: usize = 16;
+
+    let sut_problem = random_problem(PROBLEM_SIZE);
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let criteria = StoppingCriteria::new().with_cancellation_token(token);
+
+    let max_iterations = NonZeroUsize::new(usize::MAX).unwrap();
+    let mut sut = SimulatedAnnealer::new(max_iterations, NonZeroUsize::new(4).unwrap(), SimulatedAnnealer::default_temperature(max_iterations));
+    let solution = sut.solve_until(&sut_problem, &criteria);
+
+    assert_eq!(PROBLEM_SIZE, solution.len());
+}
+
+#[test]
+fn check_observers() {
+    const PROBLEM_SIZE: usize = 16;
+    const MAX_ITERATIONS: usize = 10;
+    const PARALLELISM: usize = 3;
+
+    let sut_problem = random_problem(PROBLEM_SIZE);
+
+    let observer = Arc::new(MemoryObserver::new());
+
+    let max_iterations = NonZeroUsize::new(MAX_ITERATIONS).unwrap();
+    let mut sut = SimulatedAnnealer::new(max_iterations, NonZeroUsize::new(PARALLELISM).unwrap(), SimulatedAnnealer::default_temperature(max_iterations))
+        .with_observer(observer.clone());
+    let solution = sut.solve(&sut_problem);
+
+    let events = observer.take_events();
+
+    // Every replica reports once per iteration and once more when it finishes
+    assert_eq!(PARALLELISM * (MAX_ITERATIONS + 1), events.len());
+    for replica in 0..PARALLELISM {
+        assert!(events.iter().any(|e| e.replica == replica && e.iteration == MAX_ITERATIONS));
+    }
+    assert!(events.iter().all(|e| e.best_energy <= e.energy));
+    assert!(events.iter().any(|e| e.best_energy == sut_problem.evaluate(&solution)));
+}
+
+#[test]
+fn check_json_lines_observer() {
+    let mut output = Vec::new();
+    let observer = JsonLinesObserver::new(&mut output);
+    for (energy, best_energy) in [(-1.5, f64::NEG_INFINITY), (f64::NAN, f64::INFINITY)] {
+        observer.observe(&SolverEvent { iteration: 3, energy, best_energy, replica: 1, elapsed: Duration::ZERO });
+    }
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        concat!(
+            r#"{"replica":1,"iteration":3,"elapsed":0,"energy":-1.5,"best_energy":null}"#, "\n",
+            r#"{"replica":1,"iteration":3,"elapsed":0,"energy":null,"best_energy":null}"#, "\n",
+        )
+    );
+}
+
+#[test]
+fn check_real_weights() {
+    const PROBLEM_SIZE: usize = 12;
+
+    let mut rng = thread_rng();
+
+    let q_matrix: CsrMatrix<f64> = CsrMatrix::from(&DMatrix::from_fn(PROBLEM_SIZE, PROBLEM_SIZE, |i, j| {
+        if i <= j {
+            rng.gen_range(-1.0..1.0)
+        } else {
+            0.0
+        }
+    }));
+    let sut_problem = QuboProblem::try_from_q_matrix(q_matrix).expect("Matrix is supposedly valid");
+
+    let optimal = sut_problem.evaluate(&ExhaustiveSearch::new().solve(&sut_problem));
+    let parallel_optimal = sut_problem.evaluate(&ParallelExhaustiveSearch::new(NonZeroUsize::new(3).unwrap()).solve(&sut_problem));
+
+    assert_eq!(optimal, parallel_optimal);
+
+    let max_iterations = NonZeroUsize::new(1_000).unwrap();
+    let annealed = sut_problem.evaluate(&SimulatedAnnealer::new(max_iterations, NonZeroUsize::new(4).unwrap(), SimulatedAnnealer::default_temperature(max_iterations)).solve(&sut_problem));
+
+    assert!(optimal <= annealed);
+}
+
+#[test]
+fn check_tabu_search() {
+    const PROBLEM_SIZE: usize = 12;
+
+    let sut_problem = random_problem(PROBLEM_SIZE);
+
+    let optimal = sut_problem.evaluate(&ExhaustiveSearch::new().solve(&sut_problem));
+
+    let mut sut = TabuSearch::new(NonZeroUsize::new(500).unwrap(), NonZeroUsize::new(4).unwrap())
+        .with_tenure(3)
+        .with_restarts(NonZeroUsize::new(50).unwrap());
+    let solution = sut.solve(&sut_problem);
+
+    assert_eq!(optimal, sut_problem.evaluate(&solution));
+}
+
+#[test]
+fn check_tabu_tenure() {
+    const PROBLEM_SIZE: usize = 4;
+
+    let sut_problem = random_problem(PROBLEM_SIZE);
+    let observer = Arc::new(MemoryObserver::new());
+
+    // A tenure longer than the problem is shortened so that the search never runs out of flips
+    let mut sut = TabuSearch::new(NonZeroUsize::new(20).unwrap(), NonZeroUsize::new(1).unwrap())
+        .with_tenure(100)
+        .with_aspiration(false)
+        .with_observer(observer.clone());
+    let solution = sut.solve(&sut_problem);
+
+    let events = observer.take_events();
+
+    assert_eq!(21, events.len());
+    assert!(events.iter().any(|e| e.best_energy == sut_problem.evaluate(&solution)));
+}
+
+#[test]
+fn check_parallel_tempering() {
+    const PROBLEM_SIZE: usize = 12;
+    const NB_REPLICAS: usize = 6;
+
+    let sut_problem = random_problem(PROBLEM_SIZE);
+
+    let optimal = sut_problem.evaluate(&ExhaustiveSearch::new().solve(&sut_problem));
+
+    let mut sut = ParallelTempering::new(NonZeroUsize::new(500).unwrap(), NonZeroUsize::new(NB_REPLICAS).unwrap());
+    let solution = sut.solve(&sut_problem);
+
+    assert_eq!(optimal, sut_problem.evaluate(&solution));
+
+    let statistics = sut.statistics().expect("The solver has run");
+
+    assert_eq!(NB_REPLICAS, statistics.temperatures.len());
+    assert!(statistics.temperatures.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(NB_REPLICAS - 1, statistics.attempted.len());
+    assert!(statistics.attempted.iter().all(|attempted| *attempted > 0));
+    assert!(statistics.acceptance_rates().iter().all(|rate| (0.0..=1.0).contains(rate)));
+}
+
+#[test]
+fn check_parallel_tempering_ladder() {
+    const PROBLEM_SIZE: usize = 8;
+    const MAX_SWEEPS: usize = 10;
+
+    let sut_problem = random_problem(PROBLEM_SIZE);
+    let temperatures = vec![1.0, 4.0, 16.0];
+    let observer = Arc::new(MemoryObserver::new());
+
+    let mut sut = ParallelTempering::new(NonZeroUsize::new(MAX_SWEEPS).unwrap(), NonZeroUsize::new(8).unwrap())
+        .with_temperatures(temperatures.clone())
+        .with_swap_interval(NonZeroUsize::new(2).unwrap())
+        .with_adaptation(false)
+        .with_observer(observer.clone());
+    sut.solve(&sut_problem);
+
+    let statistics = sut.statistics().expect("The solver has run");
+
+    // A fixed ladder is kept as is, and only pairs with the same parity as the swap round are tried
+    assert_eq!(temperatures, statistics.temperatures);
+    assert_eq!(vec![3, 2], statistics.attempted);
+    assert_eq!(temperatures.len() * (MAX_SWEEPS + 1), observer.take_events().len());
+
+    // A problem without coefficients gets a flat ladder, which adaptation must leave alone
+    let flat_problem: QuboProblem = QuboProblem::try_from_triplets(PROBLEM_SIZE, Vec::new()).unwrap();
+    let mut sut = ParallelTempering::new(NonZeroUsize::new(100).unwrap(), NonZeroUsize::new(4).unwrap());
+    sut.solve(&flat_problem);
+
+    assert!(sut.statistics().expect("The solver has run").temperatures.iter().all(|t| t.is_finite()));
+}
+
+#[test]
+fn check_simulated_quantum_annealing() {
+    const PROBLEM_SIZE: usize = 12;
+    const NB_SLICES: usize = 5;
+    const MAX_SWEEPS: usize = 300;
+    const SEED: u64 = 13;
+
+    let sut_problem = seeded_problem(PROBLEM_SIZE, SEED);
+
+    let optimal = sut_problem.evaluate(&ExhaustiveSearch::new().solve(&sut_problem));
+    let observer = Arc::new(MemoryObserver::new());
+
+    let mut sut = SimulatedQuantumAnnealer::new(NonZeroUsize::new(MAX_SWEEPS).unwrap(), NonZeroUsize::new(NB_SLICES).unwrap())
+        .with_seed(SEED)
+        .with_observer(observer.clone());
+    let solution = sut.solve(&sut_problem);
+
+    assert_eq!(optimal, sut_problem.evaluate(&solution));
+
+    // Every slice reports once per sweep and once more when the annealing finishes
+    let events = observer.take_events();
+    assert_eq!(NB_SLICES * (MAX_SWEEPS + 1), events.len());
+    assert!(events.iter().all(|e| e.best_energy <= e.energy));
+}
+
+#[test]
+fn check_simulated_bifurcation() {
+    const PROBLEM_SIZE: usize = 12;
+    const SEED: u64 = 14;
+
+    let sut_problem = seeded_problem(PROBLEM_SIZE, SEED);
+
+    let optimal = sut_problem.evaluate(&ExhaustiveSearch::new().solve(&sut_problem));
+
+    for variant in [BifurcationVariant::Ballistic, BifurcationVariant::Discrete] {
+        let mut sut = SimulatedBifurcation::new(NonZeroUsize::new(500).unwrap(), NonZeroUsize::new(16).unwrap())
+            .with_variant(variant)
+            .with_time_step(0.5)
+            .with_seed(SEED);
+        let solution = sut.solve(&sut_problem);
+
+        assert_eq!(optimal, sut_problem.evaluate(&solution), "{:?} did not find the optimum", variant);
+    }
+}
+
+#[test]
+fn check_branch_and_bound() {
+    const PROBLEM_SIZE: usize = 16;
+
+    for _ in 0..10 {
+        let sut_problem = random_problem(PROBLEM_SIZE);
+
+        let optimal = sut_problem.evaluate(&ExhaustiveSearch::new().solve(&sut_problem));
+
+        for order in [VariableOrder::Index, VariableOrder::Weight, VariableOrder::Dynamic] {
+            let mut sut = BranchAndBound::new().with_order(order);
+
+            assert_eq!(optimal, sut_problem.evaluate(&sut.solve(&sut_problem)), "{:?} is not optimal", order);
+        }
+    }
+}
+
+#[test]
+fn check_branch_and_bound_size() {
+    const PROBLEM_SIZE: usize = 48;
+    const BLOCK_SIZE: usize = 8;
+
+    let mut rng = thread_rng();
+
+    // Independent blocks have an optimum that is the sum of the optima of the blocks
+    let blocks: Vec<_> = (0..PROBLEM_SIZE / BLOCK_SIZE).map(|_| random_problem(BLOCK_SIZE)).collect();
+    let optimal: QuboType = blocks.iter().map(|block| block.evaluate(&ExhaustiveSearch::new().solve(block))).sum();
+
+    let mut triplets: Vec<_> = blocks
+        .iter()
+        .enumerate()
+        .flat_map(|(b, block)| {
+            block.get_sparse().triplet_iter().map(|(i, j, v)| (b * BLOCK_SIZE + i, b * BLOCK_SIZE + j, *v)).collect::<Vec<_>>()
+        })
+        .collect();
+    // Shuffling the variables hides the blocks from the index order
+    let mut permutation: Vec<usize> = (0..PROBLEM_SIZE).collect();
+    permutation.shuffle(&mut rng);
+    for (i, j, _) in triplets.iter_mut() {
+        (*i, *j) = (permutation[*i], permutation[*j]);
+    }
+    let sut_problem = QuboProblem::try_from_triplets(PROBLEM_SIZE, triplets).unwrap();
+
+    let solution = BranchAndBound::new().solve(&sut_problem);
+
+    // The triplets are those of S = Q + Q^T, which is symmetrised again and so doubles every energy
+    assert_eq!(2 * optimal, sut_problem.evaluate(&solution));
+}
+
+#[test]
+fn check_gray_code_search() {
+    const PROBLEM_SIZE: usize = 14;
+
+    for split_bits in [0, 3, PROBLEM_SIZE + 1] {
+        let sut_problem = random_problem(PROBLEM_SIZE);
+
+        let optimal = ExhaustiveSearch::new().solve(&sut_problem);
+
+        let mut sut = GrayCodeSearch::new().with_split_bits(split_bits);
+        let solution = sut.solve(&sut_problem);
+
+        assert_eq!(sut_problem.evaluate(&optimal), sut_problem.evaluate(&solution));
+        assert_eq!(optimal, solution);
+    }
+}
+
+#[test]
+fn check_gray_code_ground_states() {
+    // E(x) = 2 (x_0 + x_1 - 1)^2 - 2 + 2 x_3 has its ground states where exactly one of x_0 and x_1
+    // is set and x_3 is not, whatever the value of x_2
+    let triplets = vec![(0, 0, -1), (1, 1, -1), (0, 1, 2), (3, 3, 1)];
+    let sut_problem = QuboProblem::try_from_triplets(4, triplets).unwrap();
+
+    let ground_states = GrayCodeSearch::new().with_split_bits(2).ground_states(&sut_problem, &StoppingCriteria::new());
+
+    assert_eq!(-2, ground_states.energy);
+    assert_eq!(4, ground_states.count);
+    assert_eq!(4, ground_states.states.len());
+    for solution in &ground_states.states {
+        assert_eq!(ground_states.energy, sut_problem.evaluate(solution));
+        assert_eq!(1, solution.value(0) + solution.value(1));
+        assert_eq!(0, solution.value(3));
+    }
+}
+
+#[test]
+fn check_gray_code_stopping() {
+    const PROBLEM_SIZE: usize = 40;
+
+    let sut_problem = random_problem(PROBLEM_SIZE);
+
+    // A single range of 2^40 solutions is stopped at its first check
+    let criteria = StoppingCriteria::new().with_max_evaluations(1);
+    let solution = GrayCodeSearch::new().with_split_bits(0).solve_until(&sut_problem, &criteria);
+
+    assert_eq!(PROBLEM_SIZE, solution.len());
+}
+
+#[test]
+fn check_population_annealing() {
+    const PROBLEM_SIZE: usize = 12;
+
+    let sut_problem = random_problem(PROBLEM_SIZE);
+
+    let optimal = sut_problem.evaluate(&ExhaustiveSearch::new().solve(&sut_problem));
+
+    let mut sut = PopulationAnnealer::new(NonZeroUsize::new(100).unwrap(), NonZeroUsize::new(200).unwrap());
+    let solution = sut.solve(&sut_problem);
+
+    assert_eq!(optimal, sut_problem.evaluate(&solution));
+
+    let statistics = sut.statistics().expect("The solver has run");
+
+    assert_eq!(optimal as f64, statistics.ground_energy);
+    // With so few steps the estimate is rough, but some of the population must reach the optimum
+    assert!(statistics.degeneracy > 0.0 && statistics.degeneracy.is_finite());
+}
+
+#[test]
+fn check_population_annealing_statistics() {
+    const TEMPERATURE: f64 = 0.5;
+
+    // The same problem as check_gray_code_ground_states, with 4 ground states at energy -2
+    let triplets = vec![(0, 0, -1), (1, 1, -1), (0, 1, 2), (3, 3, 1)];
+    let sut_problem = QuboProblem::try_from_triplets(4, triplets).unwrap();
+
+    let log_partition = (0..16)
+        .map(|x| {
+            let solution = QuboSolution::from(DVector::from_fn(4, |i, _| (x >> i & 1) as QuboType));
+
+            f64::exp(-sut_problem.evaluate(&solution) as f64 / TEMPERATURE)
+        })
+        .sum::<f64>()
+        .ln();
+
+    let mut sut = PopulationAnnealer::new(NonZeroUsize::new(50).unwrap(), NonZeroUsize::new(2_000).unwrap())
+        .with_final_temperature(TEMPERATURE);
+    sut.solve(&sut_problem);
+
+    let statistics = sut.statistics().expect("The solver has run");
+
+    assert_eq!(TEMPERATURE, statistics.temperature);
+    assert!((statistics.log_partition - log_partition).abs() < 0.1, "{} vs {}", statistics.log_partition, log_partition);
+    assert!((statistics.free_energy + TEMPERATURE * log_partition).abs() < 0.1);
+    assert!((statistics.degeneracy - 4.0).abs() < 0.5, "{}", statistics.degeneracy);
+}
+
+#[test]
+fn check_schedules() {
+    let linear = Linear::new(1.0, 0.0, 4);
+    assert_eq!(vec![1.0, 0.75, 0.5, 0.25, 0.0, 0.0], (0..6).map(|k| linear.value(k)).collect::<Vec<_>>());
+
+    let geometric = Geometric::new(8.0, 1.0, 3);
+    assert_eq!(vec![8.0, 4.0, 2.0, 1.0, 1.0], (0..5).map(|k| geometric.value(k)).collect::<Vec<_>>());
+
+    let exponential = Exponential::new(2.0, f64::ln(2.0));
+    assert!((exponential.value(3) - 0.25).abs() < 1e-12);
+
+    let logarithmic = Logarithmic::new(1.0);
+    assert!(logarithmic.value(0).is_infinite());
+    assert!((logarithmic.value(1) - 1.0 / f64::ln(2.0)).abs() < 1e-12);
+
+    let piecewise = Piecewise::new(vec![(2, 1.0), (4, 3.0), (8, 0.0)]);
+    assert_eq!(vec![1.0, 1.0, 2.0, 3.0, 2.25, 0.0, 0.0], [0, 2, 3, 4, 5, 8, 9].map(|k| piecewise.value(k)).to_vec());
+
+    let custom = |k: usize| k as f64;
+    assert_eq!(3.0, Schedule::value(&custom, 3));
+}
+
+#[test]
+fn check_simulated_annealing_schedule() {
+    const PROBLEM_SIZE: usize = 12;
+
+    let sut_problem = random_problem(PROBLEM_SIZE);
+
+    let optimal = sut_problem.evaluate(&ExhaustiveSearch::new().solve(&sut_problem));
+
+    // Cooling down to zero leaves a greedy descent at the end of the run
+    let mut sut = SimulatedAnnealer::new(NonZeroUsize::new(1_000).unwrap(), NonZeroUsize::new(4).unwrap(), Linear::new(1.0, 0.0, 800));
+    let solution = sut.solve(&sut_problem);
+
+    assert!(optimal <= sut_problem.evaluate(&solution));
+}
+
+#[test]
+fn check_seeds() {
+    const PROBLEM_SIZE: usize = 24;
+    const SEED: u64 = 42;
+
+    let sut_problem = random_problem(PROBLEM_SIZE);
+    let n = |n: usize| NonZeroUsize::new(n).unwrap();
+
+    let solvers = || -> Vec<Box<dyn Solver<QuboProblem>>> {
+        vec![
+            Box::new(SimulatedAnnealer::new(n(100), n(4), SimulatedAnnealer::default_temperature(n(100))).with_seed(SEED)),
+            Box::new(MomentumAnnealer::new(100, MomentumAnnealer::default_temperature(), MomentumAnnealer::default_dropout(), MomentumAnnealer::default_momentum_scaling()).with_seed(SEED)),
+            Box::new(TabuSearch::new(n(100), n(4)).with_restarts(n(10)).with_seed(SEED)),
+            Box::new(ParallelTempering::new(n(50), n(4)).with_seed(SEED)),
+            Box::new(PopulationAnnealer::new(n(20), n(32)).with_seed(SEED)),
+            Box::new(SimulatedQuantumAnnealer::new(n(50), n(4)).with_seed(SEED)),
+            Box::new(SimulatedBifurcation::new(n(50), n(4)).with_seed(SEED)),
+            Box::new(Mopso::new(n(16), 0.7, n(20)).with_seed(SEED)),
+        ]
+    };
+
+    for (mut first, mut second) in solvers().into_iter().zip(solvers()) {
+        assert_eq!(first.solve(&sut_problem), second.solve(&sut_problem));
+    }
+
+    // The whole trajectory is reproduced, and not only the final solution
+    let trajectory = || {
+        let observer = Arc::new(MemoryObserver::new());
+        SimulatedAnnealer::new(n(50), n(4), SimulatedAnnealer::default_temperature(n(50)))
+            .with_seed(SEED)
+            .with_observer(observer.clone())
+            .solve(&sut_problem);
+
+        let mut events: Vec<_> = observer.take_events().into_iter().map(|e| (e.replica, e.iteration, e.energy)).collect();
+        events.sort();
+
+        events
+    };
+
+    assert_eq!(trajectory(), trajectory());
+}
+
+#[test]
+fn check_warm_starts() {
+    const PROBLEM_SIZE: usize = 16;
+
+    let sut_problem = random_problem(PROBLEM_SIZE);
+    let n = |n: usize| NonZeroUsize::new(n).unwrap();
+
+    let optimum = ExhaustiveSearch::new().solve(&sut_problem);
+    let optimal = sut_problem.evaluate(&optimum);
+
+    // Stopped as early as possible, the solvers can only return what they started from, or what the
+    // exhaustive searches visit right after it
+    let criteria = StoppingCriteria::new().with_max_evaluations(1);
+    let mut solvers: Vec<Box<dyn Solver<QuboProblem>>> = vec![
+        Box::new(SimulatedAnnealer::new(n(100), n(4), SimulatedAnnealer::default_temperature(n(100))).with_initial_states([optimum.clone()])),
+        Box::new(TabuSearch::new(n(100), n(4)).with_initial_states([optimum.clone()])),
+        Box::new(ParallelTempering::new(n(50), n(4)).with_initial_states([optimum.clone()])),
+        Box::new(MomentumAnnealer::new(100, MomentumAnnealer::default_temperature(), MomentumAnnealer::default_dropout(), MomentumAnnealer::default_momentum_scaling()).with_initial_state(optimum.clone())),
+        Box::new(ExhaustiveSearch::new().with_initial_state(optimum.clone())),
+        Box::new(ParallelExhaustiveSearch::new(n(2)).with_initial_state(optimum.clone())),
+    ];
+
+    for sut in &mut solvers {
+        assert_eq!(optimal, sut_problem.evaluate(&sut.solve_until(&sut_problem, &criteria)));
+    }
+
+    // The initial states are handed out to the replicas in turn
+    let starts = [QuboSolution::zeros(PROBLEM_SIZE), QuboSolution::from_fn(PROBLEM_SIZE, |_| true)];
+    let samples = SimulatedAnnealer::new(n(100), n(4), SimulatedAnnealer::default_temperature(n(100)))
+        .with_initial_states(starts.clone())
+        .sample_until(&sut_problem, &criteria);
+
+    for (i, start) in starts.iter().enumerate() {
+        let sample = samples.iter().find(|sample| sample.solution == *start).expect("Every start is returned");
+
+        assert_eq!(vec![i, i + 2], sample.replicas);
+    }
+}
+
+#[test]
+fn check_ground_state_samples() {
+    // The same problem as in check_gray_code_ground_states, with 4 ground states at -2
+    let triplets = vec![(0, 0, -1), (1, 1, -1), (0, 1, 2), (3, 3, 1)];
+    let sut_problem = QuboProblem::try_from_triplets(4, triplets).unwrap();
+
+    let mut solvers: Vec<Box<dyn Solver<QuboProblem>>> = vec![
+        Box::new(ExhaustiveSearch::new()),
+        Box::new(ParallelExhaustiveSearch::new(NonZeroUsize::new(2).unwrap())),
+        Box::new(GrayCodeSearch::new().with_split_bits(2)),
+    ];
+
+    for sut in &mut solvers {
+        let samples = sut.sample(&sut_problem);
+
+        assert_eq!(4, samples.len());
+        assert_eq!(4, samples.ground_states().count());
+        for sample in &samples {
+            assert_eq!(-2, sample.energy);
+            assert_eq!(sample.energy, sut_problem.evaluate(&sample.solution));
+        }
+        assert_eq!(sut.solve(&sut_problem), samples.best().unwrap().solution);
+    }
+
+    // Without any coefficients every solution is a ground state, and only the first few are kept
+    const FREE_SIZE: usize = 12;
+    let free_problem: QuboProblem = QuboProblem::try_from_triplets(FREE_SIZE, Vec::new()).unwrap();
+    let max_ground_states = NonZeroUsize::new(3).unwrap();
+
+    let mut solvers: Vec<Box<dyn Solver<QuboProblem>>> = vec![
+        Box::new(ExhaustiveSearch::new().with_max_ground_states(max_ground_states)),
+        Box::new(ParallelExhaustiveSearch::new(NonZeroUsize::new(4).unwrap()).with_max_ground_states(max_ground_states)),
+        Box::new(GrayCodeSearch::new().with_split_bits(4).with_max_ground_states(max_ground_states)),
+    ];
+
+    for sut in &mut solvers {
+        assert_eq!(max_ground_states.get(), sut.sample(&free_problem).len());
+    }
+
+    let ground_states = GrayCodeSearch::new()
+        .with_split_bits(4)
+        .with_max_ground_states(max_ground_states)
+        .ground_states(&free_problem, &StoppingCriteria::new());
+
+    assert_eq!(max_ground_states.get(), ground_states.states.len());
+    assert_eq!(1 << FREE_SIZE, ground_states.count);
+}
+
+#[test]
+fn check_replica_samples() {
+    const PROBLEM_SIZE: usize = 16;
+    const PARALLELISM: usize = 8;
+
+    let sut_problem = random_problem(PROBLEM_SIZE);
+    let n = |n: usize| NonZeroUsize::new(n).unwrap();
+
+    let samples = SimulatedAnnealer::new(n(20), n(PARALLELISM), SimulatedAnnealer::default_temperature(n(20)))
+        .with_seed(7)
+        .sample(&sut_problem);
+
+    // Every replica is accounted for once, even when several end on the same solution
+    assert_eq!(PARALLELISM, samples.nb_occurrences());
+    let mut replicas: Vec<_> = samples.iter().flat_map(|sample| sample.replicas.clone()).collect();
+    replicas.sort();
+    assert_eq!((0..PARALLELISM).collect::<Vec<_>>(), replicas);
+
+    for sample in &samples {
+        assert_eq!(sample.occurrences, sample.replicas.len());
+        assert_eq!(sample.energy, sut_problem.evaluate(&sample.solution));
+    }
+    assert!(samples.iter().zip(samples.iter().skip(1)).all(|(a, b)| a.energy <= b.energy));
+
+    // Mapping every solution onto the same one merges them all
+    let merged = samples.map(|_, energy| (QuboSolution::from(DVector::zeros(PROBLEM_SIZE)), energy));
+    assert_eq!(1, merged.len());
+    assert_eq!(PARALLELISM, merged.best().unwrap().occurrences);
+}
+
+#[test]
+fn check_sample_set_ordering() {
+    let solution = |bits: &[QuboType]| QuboSolution::from(DVector::from_column_slice(bits));
+
+    let samples: SampleSet<QuboSolution, QuboType> = [
+        (solution(&[1, 0]), 3, 0),
+        (solution(&[0, 1]), -1, 1),
+        (solution(&[1, 1]), -1, 2),
+        (solution(&[1, 0]), 3, 3),
+    ]
+        .into_iter()
+        .collect();
+
+    let order: Vec<_> = samples.iter().map(|sample| (sample.solution.clone(), sample.energy, sample.replicas.clone())).collect();
+    assert_eq!(
+        vec![
+            (solution(&[0, 1]), -1, vec![1]),
+            (solution(&[1, 1]), -1, vec![2]),
+            (solution(&[1, 0]), 3, vec![0, 3]),
+        ],
+        order
+    );
+    assert_eq!(2, samples.ground_states().count());
+}
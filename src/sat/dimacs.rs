@@ -0,0 +1,207 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Lines, Read};
+use std::iter::Peekable;
+
+use crate::sat::{KSatProblemError, Position, SatVariable};
+
+pub(crate) struct Token {
+    pub text: String,
+    pub position: Position,
+}
+
+/// Splits a DIMACS file into whitespace separated tokens, one line at a time. Comment lines are
+/// skipped, and a `%` ends the file, as in the SATLIB benchmarks.
+struct Tokenizer<R> {
+    lines: Lines<R>,
+    line: usize,
+    tokens: VecDeque<Token>,
+    finished: bool,
+}
+
+impl<R> Tokenizer<R>
+    where
+        R: BufRead,
+{
+    fn read_line(&mut self) -> Result<(), KSatProblemError> {
+        let Some(next_line) = self.lines.next() else {
+            self.finished = true;
+            return Ok(());
+        };
+        let next_line = next_line?;
+        self.line += 1;
+
+        let mut start = None;
+        for (column, (i, c)) in next_line.char_indices().chain([(next_line.len(), ' ')]).enumerate() {
+            match (start, c.is_whitespace()) {
+                (None, false) => start = Some((i, column)),
+                (Some((start_i, start_column)), true) => {
+                    let text = &next_line[start_i..i];
+
+                    if text.starts_with('%') {
+                        self.finished = true;
+                        break;
+                    }
+                    if text.starts_with('c') && self.tokens.is_empty() {
+                        // The whole line is a comment
+                        break;
+                    }
+
+                    self.tokens.push_back(Token {
+                        text: text.to_string(),
+                        position: Position {
+                            line: self.line,
+                            column: start_column + 1,
+                        },
+                    });
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R> Iterator for Tokenizer<R>
+    where
+        R: BufRead,
+{
+    type Item = Result<Token, KSatProblemError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.tokens.is_empty() && !self.finished {
+            if let Err(e) = self.read_line() {
+                self.finished = true;
+                return Some(Err(e));
+            }
+        }
+
+        self.tokens.pop_front().map(Ok)
+    }
+}
+
+pub(crate) struct Header {
+    pub nb_vars: usize,
+    pub nb_clauses: usize,
+    pub top: Option<u64>,
+}
+
+/// Reads the header and clauses of a DIMACS file. Clauses are terminated by a `0` and may span
+/// several lines or share a line with other clauses.
+pub(crate) struct DimacsReader<R>
+    where
+        R: BufRead,
+{
+    tokens: Peekable<Tokenizer<R>>,
+    nb_vars: Option<usize>,
+}
+
+impl<R> DimacsReader<BufReader<R>>
+    where
+        R: Read,
+{
+    pub fn new(file: R) -> Self {
+        Self {
+            tokens: Tokenizer {
+                lines: BufReader::new(file).lines(),
+                line: 0,
+                tokens: VecDeque::new(),
+                finished: false,
+            }
+            .peekable(),
+            nb_vars: None,
+        }
+    }
+}
+
+impl<R> DimacsReader<R>
+    where
+        R: BufRead,
+{
+    pub fn next_token(&mut self) -> Result<Option<Token>, KSatProblemError> {
+        self.tokens.next().transpose()
+    }
+
+    /// Reads a `p <format> <nb_vars> <nb_clauses> [top]` line if the file starts with one. The top
+    /// weight is only accepted for `wcnf` files.
+    pub fn header(&mut self, format: &str) -> Result<Option<Header>, KSatProblemError> {
+        match self.tokens.peek() {
+            Some(Ok(token)) if token.text == "p" => {}
+            Some(Err(_)) => return Err(self.next_token().err().expect("Peeked an error")),
+            _ => return Ok(None),
+        }
+
+        let p_token = self.next_token()?.expect("Peeked a token");
+        let mut fields = Vec::new();
+
+        while let Some(Ok(token)) = self.tokens.peek() {
+            if token.position.line != p_token.position.line {
+                break;
+            }
+
+            fields.push(self.next_token()?.expect("Peeked a token"));
+        }
+
+        let invalid_header = || KSatProblemError::InvalidHeader { position: p_token.position };
+        let number = |token: &Token| token.text.parse().or(Err(invalid_header()));
+
+        let header = match fields.as_slice() {
+            [f, nb_vars, nb_clauses] if f.text == format => Header {
+                nb_vars: number(nb_vars)?,
+                nb_clauses: number(nb_clauses)?,
+                top: None,
+            },
+            [f, nb_vars, nb_clauses, top] if f.text == format && format == "wcnf" => Header {
+                nb_vars: number(nb_vars)?,
+                nb_clauses: number(nb_clauses)?,
+                top: Some(top.text.parse().or(Err(invalid_header()))?),
+            },
+            _ => return Err(invalid_header()),
+        };
+
+        self.nb_vars = Some(header.nb_vars);
+
+        Ok(Some(header))
+    }
+
+    /// Reads literals up to and including the terminating `0` of a clause starting at `start`
+    pub fn literals(&mut self, start: Position) -> Result<Vec<SatVariable>, KSatProblemError> {
+        let mut clause = Vec::new();
+
+        loop {
+            let Some(Token { text, position }) = self.next_token()? else {
+                return Err(KSatProblemError::UnterminatedClause { position: start });
+            };
+
+            let literal: isize = text
+                .parse()
+                .or(Err(KSatProblemError::InvalidLiteral { token: text.clone(), position }))?;
+
+            if literal == 0 {
+                return Ok(clause);
+            }
+
+            let variable = literal.unsigned_abs() - 1;
+            if self.nb_vars.is_some_and(|nb_vars| variable >= nb_vars) {
+                return Err(KSatProblemError::InvalidVariable {
+                    variable: literal.unsigned_abs(),
+                    position,
+                });
+            }
+
+            clause.push(SatVariable(literal > 0, variable));
+        }
+    }
+
+    /// Reads the next clause along with the position it starts at, or `None` at the end of the file
+    pub fn clause(&mut self) -> Result<Option<(Position, Vec<SatVariable>)>, KSatProblemError> {
+        let start = match self.tokens.peek() {
+            None => return Ok(None),
+            Some(Ok(token)) => token.position,
+            Some(Err(_)) => return Err(self.next_token().err().expect("Peeked an error")),
+        };
+
+        self.literals(start).map(|clause| Some((start, clause)))
+    }
+}
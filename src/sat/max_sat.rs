@@ -0,0 +1,205 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::io::Read;
+
+use log::{debug, trace, warn};
+use nalgebra::DVector;
+
+use crate::core::Problem;
+use crate::sat::dimacs::{DimacsReader, Header, Token};
+use crate::sat::{KSatProblemError, SatVariable};
+
+pub type SatWeight = u64;
+
+#[derive(Clone)]
+pub enum MaxSatSolution {
+    /// An assignment that satisfies every hard clause, along with the total weight of the soft
+    /// clauses it violates
+    Sat(DVector<bool>, SatWeight),
+    Unsat,
+    Unknown,
+}
+
+impl MaxSatSolution {
+    pub fn assignment(&self) -> Option<&DVector<bool>> {
+        match self {
+            Self::Sat(assignment, _) => Some(assignment),
+            _ => None,
+        }
+    }
+}
+
+impl Debug for MaxSatSolution {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sat(assignment, cost) => write!(
+                f,
+                "Sat with cost {} ({})",
+                cost,
+                assignment
+                    .iter()
+                    .enumerate()
+                    .map(|(i, x)| format!("{}{}", if *x { "" } else { "¬" }, i))
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            ),
+            Self::Unsat => write!(f, "Unsat"),
+            Self::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+impl Display for MaxSatSolution {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sat(_, cost) => write!(f, "SAT {}", cost),
+            Self::Unsat => write!(f, "UNSAT"),
+            Self::Unknown => write!(f, "UNKNOWN"),
+        }
+    }
+}
+
+// Weighted partial MaxSAT problem in conjunctive normal form with N variables
+#[derive(Clone)]
+pub struct MaxSatProblem {
+    pub nb_vars: usize,
+    pub hard_clauses: Vec<Vec<SatVariable>>,
+    pub soft_clauses: Vec<(SatWeight, Vec<SatVariable>)>,
+}
+
+impl Problem for MaxSatProblem {
+    type Solution = MaxSatSolution;
+    // The total weight of the unsatisfied soft clauses
+    type Energy = SatWeight;
+}
+
+fn is_satisfied(clause: &[SatVariable], assignment: &DVector<bool>) -> bool {
+    clause
+        .iter()
+        .any(|&SatVariable(is_pos, number)| !(is_pos ^ assignment[number]))
+}
+
+impl MaxSatProblem {
+    /// Reads both the pre-2022 `p wcnf` format, where clauses with a weight of at least `top` are
+    /// hard, and the headerless MaxSAT Evaluation format, where hard clauses start with `h`.
+    pub fn from_benchmark_file(file: impl Read) -> Result<Self, KSatProblemError> {
+        let mut reader = DimacsReader::new(file);
+
+        let header = reader.header("wcnf")?;
+        let top = header.as_ref().and_then(|header| header.top);
+
+        let mut max_var = 0;
+        let mut hard_clauses = Vec::new();
+        let mut soft_clauses = Vec::new();
+
+        while let Some(Token { text, position }) = reader.next_token()? {
+            let weight = match text.as_str() {
+                "h" => None,
+                _ => {
+                    let weight: SatWeight = text
+                        .parse()
+                        .or(Err(KSatProblemError::InvalidWeight { token: text.clone(), position }))?;
+
+                    top.filter(|&top| weight >= top).map_or(Some(weight), |_| None)
+                }
+            };
+
+            let clause = reader.literals(position)?;
+
+            max_var = clause.iter().map(|f| f.1 + 1).fold(max_var, usize::max);
+
+            match weight {
+                None => hard_clauses.push(clause),
+                Some(weight) => soft_clauses.push((weight, clause)),
+            }
+        }
+
+        let nb_vars = match header {
+            Some(Header { nb_vars, nb_clauses, .. }) => {
+                if hard_clauses.len() + soft_clauses.len() != nb_clauses {
+                    warn!(
+                        "Header declares {} clauses but {} were found",
+                        nb_clauses,
+                        hard_clauses.len() + soft_clauses.len()
+                    );
+                }
+
+                nb_vars
+            }
+            None => max_var,
+        };
+
+        Ok(MaxSatProblem {
+            nb_vars,
+            hard_clauses,
+            soft_clauses,
+        })
+    }
+
+    /// Returns the total weight of the violated soft clauses, or `None` if a hard clause is violated
+    pub fn cost(&self, assignment: &DVector<bool>) -> Option<SatWeight> {
+        assert_eq!(
+            self.nb_vars,
+            assignment.len(),
+            "Assignment is not same size as number of variables"
+        );
+
+        if let Some(clause) = self.hard_clauses.iter().find(|clause| !is_satisfied(clause, assignment)) {
+            debug!("Hard clause violated! {:?}", clause);
+
+            return None;
+        }
+
+        let cost = self
+            .soft_clauses
+            .iter()
+            .filter(|(_, clause)| !is_satisfied(clause, assignment))
+            .inspect(|(weight, clause)| trace!("Soft clause violated with weight {}: {:?}", weight, clause))
+            .map(|(weight, _)| weight)
+            .sum();
+
+        Some(cost)
+    }
+
+    /// Scores an assignment. An assignment that violates a hard clause does not prove that the
+    /// problem is unsatisfiable, so it produces an unknown solution.
+    pub fn solution_from_assignment(&self, assignment: DVector<bool>) -> MaxSatSolution {
+        match self.cost(&assignment) {
+            Some(cost) => MaxSatSolution::Sat(assignment, cost),
+            None => MaxSatSolution::Unknown,
+        }
+    }
+}
+
+impl Debug for MaxSatProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let format_clause = |clause: &Vec<SatVariable>| {
+            format!(
+                "({})",
+                clause
+                    .iter()
+                    .map(|SatVariable(is_pos, number)| format!(
+                        "{}{}",
+                        if *is_pos { "" } else { "¬" },
+                        number
+                    ))
+                    .collect::<Vec<String>>()
+                    .join(" + ")
+            )
+        };
+
+        let hard = self
+            .hard_clauses
+            .iter()
+            .map(format_clause)
+            .collect::<Vec<String>>()
+            .join(" . ");
+        let soft = self
+            .soft_clauses
+            .iter()
+            .map(|(weight, clause)| format!("{}{}", weight, format_clause(clause)))
+            .collect::<Vec<String>>()
+            .join(" + ");
+
+        write!(f, "MaxSatProblem hard {} soft {}", hard, soft)
+    }
+}
@@ -1,4 +1,10 @@
+pub use max_sat::*;
 pub use problem::*;
 
 pub mod reductions;
+mod dimacs;
+mod max_sat;
 mod problem;
+
+#[cfg(test)]
+mod test;
@@ -1,13 +1,13 @@
 use std::fmt::{Debug, Formatter};
 use std::fmt::Display;
-use std::io::{BufRead, BufReader, Read};
+use std::io::Read;
 
-use log::{debug, trace};
+use log::{debug, trace, warn};
 use nalgebra::DVector;
-use regex::Regex;
 use thiserror::Error;
 
 use crate::core::Problem;
+use crate::sat::dimacs::{DimacsReader, Header};
 
 #[derive(Clone)]
 pub enum SatSolution {
@@ -31,6 +31,15 @@ impl Debug for SatVariable {
     }
 }
 
+impl SatSolution {
+    pub fn assignment(&self) -> Option<&DVector<bool>> {
+        match self {
+            Self::Sat(assignment) => Some(assignment),
+            _ => None,
+        }
+    }
+}
+
 impl Debug for SatSolution {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -66,89 +75,68 @@ pub struct KSatProblem {
 
 impl Problem for KSatProblem {
     type Solution = SatSolution;
+    // The number of unsatisfied clauses
+    type Energy = usize;
+}
+
+/// A position in a DIMACS file. Lines and columns both start at 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum KSatProblemError {
     #[error("Error reading the file")]
     FileError(#[from] std::io::Error),
-    #[error("Incorrect File Header")]
-    InvalidHeader,
-    #[error("Invalid variable declared in file")]
-    InvalidVariable,
+    #[error("File missing instance header")]
+    MissingHeader,
+    #[error("Incorrect File Header at {position}")]
+    InvalidHeader { position: Position },
+    #[error("Invalid variable {variable} declared at {position}")]
+    InvalidVariable { variable: usize, position: Position },
+    #[error("Invalid literal `{token}` found at {position}")]
+    InvalidLiteral { token: String, position: Position },
+    #[error("Invalid clause weight `{token}` found at {position}")]
+    InvalidWeight { token: String, position: Position },
+    #[error("Clause starting at {position} is not terminated by a 0")]
+    UnterminatedClause { position: Position },
+    #[error("Empty clause found at {position}")]
+    EmptyClause { position: Position },
 }
 
 impl KSatProblem {
     pub fn from_benchmark_file(file: impl Read) -> Result<Self, KSatProblemError> {
-        let buffered_file = BufReader::new(file);
-        let mut line_result_iterator = buffered_file.lines();
-        let mut next_line: String;
-
-        while {
-            let next_line_result = line_result_iterator.next();
-            next_line = next_line_result
-                .expect("File missing instance header")
-                .map_err(KSatProblemError::FileError)?;
-
-            next_line.starts_with('c')
-        } {
-            // Consume beginning comment lines
-        }
+        let mut reader = DimacsReader::new(file);
 
-        // next_line should now be the instance header
-        let benchmark_header_re = Regex::new(r"^p cnf (?<nb_var>\d+) (?<nb_clauses>\d+)$")
-            .expect("Failed to compile the benchmark header RegEx!");
-        let captures = benchmark_header_re
-            .captures(next_line.as_str())
-            .ok_or(KSatProblemError::InvalidHeader)?;
-
-        let nb_clauses: usize = captures["nb_clauses"]
-            .parse()
-            .or(Err(KSatProblemError::InvalidHeader))?;
-        let nb_var: usize = captures["nb_var"]
-            .parse()
-            .or(Err(KSatProblemError::InvalidHeader))?;
-
-        let mut clauses = Vec::with_capacity(nb_clauses);
-
-        for line_result in line_result_iterator.take(nb_clauses) {
-            next_line = line_result.map_err(KSatProblemError::FileError)?;
-
-            let mut clause_line: Vec<isize> = next_line
-                .split_whitespace()
-                .map(|x| {
-                    x.parse::<isize>().unwrap_or_else(|_| {
-                        panic!("Unexpected non integer found while parsing clause: {}", x)
-                    })
-                })
-                .collect();
-
-            let end_number = clause_line.pop().expect("Clause line is empty!");
-            assert_eq!(
-                end_number, 0,
-                "Clause line incorrectly ends with something other than 0: {}",
-                end_number
-            );
-            assert!(
-                clause_line.iter().all(|x| *x != 0),
-                "Clause line contains values of 0: {:?}",
-                clause_line
-            );
-
-            let clause: Vec<SatVariable> = clause_line
-                .iter()
-                .map(|x| SatVariable(x.signum() > 0, x.unsigned_abs() - 1))
-                .collect();
+        let Header { nb_vars, nb_clauses, .. } = reader
+            .header("cnf")?
+            .ok_or(KSatProblemError::MissingHeader)?;
 
-            if clause.iter().any(|f| f.1 >= nb_var) {
-                return Err(KSatProblemError::InvalidVariable);
+        // The declared clause count is not trusted with the size of the buffer
+        let mut clauses = Vec::new();
+
+        while let Some((position, clause)) = reader.clause()? {
+            if clause.is_empty() {
+                return Err(KSatProblemError::EmptyClause { position });
             }
 
             clauses.push(clause);
         }
 
+        if clauses.len() != nb_clauses {
+            warn!("Header declares {} clauses but {} were found", nb_clauses, clauses.len());
+        }
+
         Ok(KSatProblem {
-            nb_vars: nb_var,
+            nb_vars,
             clause_list: clauses,
         })
     }
@@ -1,2 +1,8 @@
 #[cfg(feature = "qubo")]
-pub mod qubo;
\ No newline at end of file
+pub mod qubo;
+pub mod three_sat;
+
+#[cfg(test)]
+mod test;
+
+const NO_ASSIGNMENT: &str = "Only solutions with an assignment can be down-modelled";
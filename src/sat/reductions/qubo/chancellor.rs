@@ -1,8 +1,12 @@
 use nalgebra::DVector;
 
-use crate::core::Reduction;
+use crate::core::{DownModel, Reduction};
+use crate::ising::{IsingProblem, IsingSolution};
+use crate::ising::reductions::IsingToQubo;
 use crate::qubo::{QuboProblem, QuboSolution, QuboType};
 use crate::sat::{KSatProblem, SatSolution, SatVariable};
+use crate::sat::reductions::NO_ASSIGNMENT;
+use crate::sat::reductions::qubo::descend_auxiliaries;
 
 pub struct Chancellor(usize);
 
@@ -65,13 +69,13 @@ pub(crate) fn implement_clause(
     }
 }
 
-impl Reduction<KSatProblem, QuboProblem> for Chancellor {
+impl Reduction<KSatProblem, IsingProblem> for Chancellor {
     fn reduce(
         &KSatProblem {
             nb_vars,
             ref clause_list,
         }: &KSatProblem,
-    ) -> (QuboProblem, Self) {
+    ) -> (IsingProblem, Self) {
         let mut problem_size = nb_vars;
         let mut j_triplets = Vec::new();
         let mut j_biases = Vec::new();
@@ -81,14 +85,35 @@ impl Reduction<KSatProblem, QuboProblem> for Chancellor {
                 implement_clause(problem_size, j_triplets, j_biases, clause);
         }
 
-        let (q_matrix, _) =
-            QuboProblem::try_from_ising_triplets(problem_size, j_triplets, j_biases)
-                .expect("Matrix should be properly constructed.");
+        let ising_problem = IsingProblem::try_from_triplets(problem_size, j_triplets, j_biases, 0)
+            .expect("Matrix should be properly constructed.");
+
+        (ising_problem, Self(nb_vars))
+    }
+
+    fn up_model(&self, IsingSolution(spins): IsingSolution) -> SatSolution {
+        SatSolution::Sat(DVector::from_fn(self.0, |i, _| spins[i] > 0))
+    }
+}
+
+impl Reduction<KSatProblem, QuboProblem> for Chancellor {
+    fn reduce(ksat_problem: &KSatProblem) -> (QuboProblem, Self) {
+        let (ising_problem, chancellor): (IsingProblem, Self) = Reduction::reduce(ksat_problem);
+        let (q_matrix, _) = IsingToQubo::reduce(&ising_problem);
+
+        (q_matrix, chancellor)
+    }
 
-        (q_matrix, Self(nb_vars))
+    fn up_model(&self, solution: QuboSolution) -> SatSolution {
+        SatSolution::Sat(DVector::from_fn(self.0, |i, _| solution.get(i)))
     }
+}
+
+impl DownModel<KSatProblem, QuboProblem> for Chancellor {
+    fn down_model(&self, qubo_problem: &QuboProblem, solution: SatSolution) -> QuboSolution {
+        let assignment = solution.assignment().expect(NO_ASSIGNMENT);
+        let solution = QuboSolution::from_fn(qubo_problem.get_size(), |i| i < self.0 && assignment[i]);
 
-    fn up_model(&self, QuboSolution(solution_vector): QuboSolution) -> SatSolution {
-        SatSolution::Sat(DVector::from_fn(self.0, |i, _| solution_vector[i] != 0))
+        descend_auxiliaries(qubo_problem, solution, self.0)
     }
 }
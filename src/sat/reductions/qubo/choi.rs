@@ -2,9 +2,11 @@ use log::{trace, warn};
 use nalgebra::DVector;
 use nalgebra_sparse::{CooMatrix, CsrMatrix};
 
-use crate::core::Reduction;
+use crate::core::{DownModel, Reduction};
 use crate::qubo::{QuboProblem, QuboSolution, QuboType};
 use crate::sat::{KSatProblem, SatSolution, SatVariable};
+use crate::sat::reductions::NO_ASSIGNMENT;
+use crate::sat::reductions::qubo::descend_auxiliaries;
 
 // Choi scales directly in the number of clause variables and therefore the size of the problem
 pub struct Choi {
@@ -74,8 +76,6 @@ impl Reduction<KSatProblem, QuboProblem> for Choi {
     }
 
     fn up_model(&self, qubo_solution: QuboSolution) -> SatSolution {
-        let QuboSolution(solution_vector) = qubo_solution;
-
         let mut output_vector: Vec<bool> = Vec::with_capacity(self.map.len());
         for i in 0..self.map.len() {
             let (true_reference_list, false_reference_list) = &self.map[i];
@@ -84,24 +84,24 @@ impl Reduction<KSatProblem, QuboProblem> for Choi {
                 // Only check for false references if there are no true references
                 !false_reference_list
                     .iter()
-                    .map(|x| solution_vector[*x])
+                    .map(|x| qubo_solution.value(*x))
                     .any(|x| x == 1)
             } else if false_reference_list.is_empty() {
                 // Only check for true references if there are no false references
                 true_reference_list
                     .iter()
-                    .map(|x| solution_vector[*x])
+                    .map(|x| qubo_solution.value(*x))
                     .any(|x| x == 1)
             } else {
                 // There is a positive assertion that x is true
                 let is_true = !true_reference_list
                     .iter()
-                    .map(|x| solution_vector[*x])
+                    .map(|x| qubo_solution.value(*x))
                     .any(|x| x == 0);
                 // There is a positive assertion that x is false
                 let is_false = !false_reference_list
                     .iter()
-                    .map(|x| solution_vector[*x])
+                    .map(|x| qubo_solution.value(*x))
                     .any(|x| x == 0);
 
                 if !(is_true || is_false) {
@@ -123,3 +123,22 @@ impl Reduction<KSatProblem, QuboProblem> for Choi {
         SatSolution::Sat(DVector::from_vec(output_vector))
     }
 }
+
+impl DownModel<KSatProblem, QuboProblem> for Choi {
+    /// Picks every occurrence of a true literal, and then drops or adds picks while it lowers the
+    /// energy, so that each satisfied clause ends up with a single pick
+    fn down_model(&self, qubo_problem: &QuboProblem, solution: SatSolution) -> QuboSolution {
+        let assignment = solution.assignment().expect(NO_ASSIGNMENT);
+
+        let mut solution = QuboSolution::zeros(qubo_problem.get_size());
+        for (i, (true_reference_list, false_reference_list)) in self.map.iter().enumerate() {
+            let picked = if assignment[i] { true_reference_list } else { false_reference_list };
+
+            for &reference in picked {
+                solution.set(reference, true);
+            }
+        }
+
+        descend_auxiliaries(qubo_problem, solution, 0)
+    }
+}
@@ -1,5 +1,23 @@
+use crate::qubo::{LocalFieldState, QuboProblem, QuboSolution, QuboWeight};
+
 pub mod chancellor;
 pub mod choi;
 pub mod nusslein;
 pub mod nusslein23;
 pub mod nusslein23_2;
+pub mod weighted_penalty;
+
+#[cfg(test)]
+mod test;
+
+/// Sets the bits from `first_auxiliary` onwards, which the reductions add on top of the variables,
+/// to a local minimum of the energy given the other bits of the solution
+fn descend_auxiliaries<T>(qubo_problem: &QuboProblem<T>, solution: QuboSolution, first_auxiliary: usize) -> QuboSolution
+    where
+        T: QuboWeight,
+{
+    let mut state = LocalFieldState::new(qubo_problem, solution);
+    state.descend(qubo_problem, first_auxiliary..qubo_problem.get_size());
+
+    state.into_solution()
+}
@@ -1,8 +1,10 @@
 use nalgebra::DVector;
 
-use crate::core::Reduction;
+use crate::core::{DownModel, Reduction};
 use crate::qubo::{QuboProblem, QuboSolution, QuboType};
 use crate::sat::{KSatProblem, SatSolution, SatVariable};
+use crate::sat::reductions::NO_ASSIGNMENT;
+use crate::sat::reductions::qubo::descend_auxiliaries;
 use crate::utils;
 
 pub struct Nusslein {
@@ -146,7 +148,7 @@ fn implement_clause(
             triplets.push((var_j, problem_size, 1));
             triplets.push((var_k, var_k, -1));
             triplets.push((var_k, problem_size, 1));
-            triplets.push((problem_size, problem_size, 1));
+            triplets.push((problem_size, problem_size, -1));
 
             (problem_size + 1, constant_factor, triplets)
         }
@@ -213,9 +215,18 @@ impl Reduction<KSatProblem, QuboProblem> for Nusslein {
         )
     }
 
-    fn up_model(&self, QuboSolution(solution_vector): QuboSolution) -> SatSolution {
+    fn up_model(&self, solution: QuboSolution) -> SatSolution {
         SatSolution::Sat(DVector::from_fn(self.nb_vars, |i, _| {
-            solution_vector[i] != 0
+            solution.get(i)
         }))
     }
 }
+
+impl DownModel<KSatProblem, QuboProblem> for Nusslein {
+    fn down_model(&self, qubo_problem: &QuboProblem, solution: SatSolution) -> QuboSolution {
+        let assignment = solution.assignment().expect(NO_ASSIGNMENT);
+        let solution = QuboSolution::from_fn(qubo_problem.get_size(), |i| i < self.nb_vars && assignment[i]);
+
+        descend_auxiliaries(qubo_problem, solution, self.nb_vars)
+    }
+}
@@ -1,9 +1,11 @@
 use nalgebra::DVector;
 use nalgebra_sparse::CooMatrix;
 
-use crate::core::Reduction;
+use crate::core::{DownModel, Reduction};
 use crate::qubo::{QuboProblem, QuboSolution, QuboType};
 use crate::sat::{KSatProblem, SatSolution, SatVariable};
+use crate::sat::reductions::NO_ASSIGNMENT;
+use crate::sat::reductions::qubo::descend_auxiliaries;
 
 pub struct Nusslein23 {
     pub nb_vars: usize,
@@ -80,9 +82,18 @@ impl Reduction<KSatProblem, QuboProblem> for Nusslein23 {
         (problem, Self { nb_vars })
     }
 
-    fn up_model(&self, QuboSolution(solution_vector): QuboSolution) -> SatSolution {
-        let out_sv = DVector::from_fn(self.nb_vars, |i, _| solution_vector[i] != 0);
+    fn up_model(&self, solution: QuboSolution) -> SatSolution {
+        let out_sv = DVector::from_fn(self.nb_vars, |i, _| solution.get(i));
 
         SatSolution::Sat(out_sv)
     }
 }
+
+impl DownModel<KSatProblem, QuboProblem> for Nusslein23 {
+    fn down_model(&self, qubo_problem: &QuboProblem, solution: SatSolution) -> QuboSolution {
+        let assignment = solution.assignment().expect(NO_ASSIGNMENT);
+        let solution = QuboSolution::from_fn(qubo_problem.get_size(), |i| i < self.nb_vars && assignment[i]);
+
+        descend_auxiliaries(qubo_problem, solution, self.nb_vars)
+    }
+}
@@ -2,9 +2,11 @@ use log::{trace, warn};
 use nalgebra::{DMatrix, DVector};
 use nalgebra_sparse::CooMatrix;
 
-use crate::core::Reduction;
+use crate::core::{DownModel, Reduction};
 use crate::qubo::{QuboProblem, QuboSolution, QuboType};
 use crate::sat::{KSatProblem, SatSolution, SatVariable};
+use crate::sat::reductions::NO_ASSIGNMENT;
+use crate::sat::reductions::qubo::descend_auxiliaries;
 
 fn fast_mod2(x: usize) -> usize {
     x & 0x1
@@ -72,10 +74,10 @@ impl Reduction<KSatProblem, QuboProblem> for Nusslein23_2 {
         (problem, Self { nb_vars })
     }
 
-    fn up_model(&self, QuboSolution(solution_vector): QuboSolution) -> SatSolution {
+    fn up_model(&self, solution: QuboSolution) -> SatSolution {
         let out_sv = DVector::from_fn(self.nb_vars, |i, _| {
-            let is_true = solution_vector[2 * i] != 0;
-            let is_false = solution_vector[2 * i + 1] != 0;
+            let is_true = solution.get(2 * i);
+            let is_false = solution.get(2 * i + 1);
 
             if !(is_true || is_false) {
                 // This variable is never chosen as the true variable in any clause, and therefore must be assumed false
@@ -94,3 +96,16 @@ impl Reduction<KSatProblem, QuboProblem> for Nusslein23_2 {
         SatSolution::Sat(out_sv)
     }
 }
+
+impl DownModel<KSatProblem, QuboProblem> for Nusslein23_2 {
+    fn down_model(&self, qubo_problem: &QuboProblem, solution: SatSolution) -> QuboSolution {
+        let assignment = solution.assignment().expect(NO_ASSIGNMENT);
+
+        // Bit 2i is set when variable i is true, and bit 2i + 1 when it is false
+        let solution = QuboSolution::from_fn(qubo_problem.get_size(), |i| {
+            i < 2 * self.nb_vars && assignment[i / 2] == (fast_mod2(i) == 0)
+        });
+
+        descend_auxiliaries(qubo_problem, solution, 2 * self.nb_vars)
+    }
+}
@@ -0,0 +1,210 @@
+use nalgebra::DVector;
+use rand::prelude::*;
+use rand::seq::index;
+
+use crate::core::{Chain, DownModel, Reduction, Solver};
+use crate::qubo::{QuboError, QuboProblem, QuboSolution};
+use crate::qubo::solvers::ExhaustiveSearch;
+use crate::sat::reductions::qubo::chancellor::Chancellor;
+use crate::sat::reductions::qubo::nusslein::Nusslein;
+use crate::sat::reductions::qubo::nusslein23::Nusslein23;
+use crate::sat::reductions::qubo::weighted_penalty::WeightedPenalty;
+use crate::sat::reductions::three_sat::ThreeSat;
+use crate::sat::{KSatProblem, MaxSatProblem, MaxSatSolution, SatSolution, SatVariable};
+
+fn random_clause(rng: &mut impl Rng, nb_vars: usize, max_length: usize) -> Vec<SatVariable> {
+    (0..rng.gen_range(1..=max_length))
+        .map(|_| SatVariable(rng.gen_bool(0.5), rng.gen_range(0..nb_vars)))
+        .collect()
+}
+
+#[test]
+fn check_weighted_penalty() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let nb_vars = 4;
+
+    for _ in 0..20 {
+        let problem = MaxSatProblem {
+            nb_vars,
+            hard_clauses: (0..2).map(|_| random_clause(&mut rng, nb_vars, 5)).collect(),
+            soft_clauses: (0..4)
+                .map(|_| (rng.gen_range(1..10), random_clause(&mut rng, nb_vars, 5)))
+                .collect(),
+        };
+
+        let (qubo_problem, reducer) = WeightedPenalty::reduce(&problem);
+        let hard_weight = 1 + problem.soft_clauses.iter().map(|(w, _)| *w as i64).sum::<i64>();
+        let nb_aux = qubo_problem.get_size() - nb_vars;
+
+        // Minimising over the auxiliary variables must give back exactly the weighted penalty
+        for x in 0..1usize << nb_vars {
+            let assignment = DVector::from_fn(nb_vars, |i, _| x >> i & 1 == 1);
+            let nb_hard_violated = problem
+                .hard_clauses
+                .iter()
+                .filter(|clause| !clause.iter().any(|&SatVariable(is_pos, i)| is_pos == assignment[i]))
+                .count() as i64;
+            let soft_cost = MaxSatProblem { hard_clauses: Vec::new(), ..problem.clone() }
+                .cost(&assignment)
+                .unwrap() as i64;
+
+            let min_energy = (0..1usize << nb_aux)
+                .map(|aux| {
+                    let solution = QuboSolution::from(DVector::from_fn(qubo_problem.get_size(), |i, _| {
+                        if i < nb_vars { (x >> i & 1) as i32 } else { (aux >> (i - nb_vars) & 1) as i32 }
+                    }));
+
+                    qubo_problem.evaluate(&solution)
+                })
+                .min()
+                .unwrap();
+
+            assert_eq!(min_energy % 2, 0);
+            assert_eq!(min_energy / 2 + reducer.offset(), hard_weight * nb_hard_violated + soft_cost);
+        }
+
+        let expected = (0..1usize << nb_vars)
+            .filter_map(|x| problem.cost(&DVector::from_fn(nb_vars, |i, _| x >> i & 1 == 1)))
+            .min();
+
+        match (reducer.up_model(ExhaustiveSearch::default().solve(&qubo_problem)), expected) {
+            (MaxSatSolution::Sat(_, cost), Some(optimum)) => assert_eq!(cost, optimum),
+            (MaxSatSolution::Unknown, None) => {}
+            (solution, expected) => panic!("Got {:?} but expected {:?}", solution, expected),
+        }
+    }
+}
+
+#[test]
+fn check_weighted_penalty_overflow() {
+    let clause = |nb_literals| (0..nb_literals).map(|i| SatVariable(true, i)).collect::<Vec<_>>();
+    let half = i64::MAX as u64 / 2;
+
+    // The hard weight is one more than the total soft weight
+    let problem = MaxSatProblem {
+        nb_vars: 4,
+        hard_clauses: vec![clause(1)],
+        soft_clauses: vec![(half, clause(1)), (half, clause(2))],
+    };
+    assert!(matches!(WeightedPenalty::try_reduce(&problem), Err(QuboError::Overflow)));
+
+    // The Rosenberg penalty of a long clause triples its weight
+    let problem = MaxSatProblem {
+        nb_vars: 4,
+        hard_clauses: Vec::new(),
+        soft_clauses: vec![(half, clause(4))],
+    };
+    assert!(matches!(WeightedPenalty::try_reduce(&problem), Err(QuboError::Overflow)));
+
+    let problem = MaxSatProblem {
+        nb_vars: 1,
+        hard_clauses: Vec::new(),
+        soft_clauses: vec![(u64::MAX, clause(1))],
+    };
+    assert!(matches!(WeightedPenalty::try_reduce(&problem), Err(QuboError::Overflow)));
+
+    let problem = MaxSatProblem {
+        nb_vars: 4,
+        hard_clauses: vec![clause(4)],
+        soft_clauses: vec![(1000, clause(4))],
+    };
+    assert!(WeightedPenalty::try_reduce(&problem).is_ok());
+}
+
+#[test]
+fn check_chained_chancellor() {
+    // Chancellor's gadget only handles clauses of three literals, so longer and shorter clauses go
+    // through 3-SAT first
+    let problem = KSatProblem {
+        nb_vars: 3,
+        clause_list: vec![
+            vec![SatVariable(true, 0), SatVariable(false, 1), SatVariable(true, 2), SatVariable(true, 1), SatVariable(false, 0)],
+            vec![SatVariable(false, 2)],
+            vec![SatVariable(false, 0), SatVariable(true, 1)],
+        ],
+    };
+
+    let (qubo_problem, reducer): (QuboProblem, _) = Chain::<ThreeSat, Chancellor, KSatProblem>::reduce(&problem);
+    let solution = Reduction::<KSatProblem, QuboProblem>::up_model(&reducer, ExhaustiveSearch::default().solve(&qubo_problem));
+
+    assert!(matches!(solution, SatSolution::Sat(_)));
+    assert!(problem.evaluate(&solution));
+}
+
+fn check_planted_down_model<R>(problem: &KSatProblem, planted: &DVector<bool>)
+    where
+        R: DownModel<KSatProblem, QuboProblem>,
+{
+    let (qubo_problem, reducer) = R::reduce(problem);
+    let solution = reducer.down_model(&qubo_problem, SatSolution::Sat(planted.clone()));
+
+    // A satisfying assignment is down-modelled onto a ground state, which comes back up unchanged
+    assert_eq!(qubo_problem.get_size(), solution.len());
+    assert_eq!(
+        qubo_problem.evaluate(&ExhaustiveSearch::new().solve(&qubo_problem)),
+        qubo_problem.evaluate(&solution)
+    );
+    match reducer.up_model(solution) {
+        SatSolution::Sat(assignment) => assert_eq!(*planted, assignment),
+        solution => panic!("Got {:?} but expected {:?}", solution, planted),
+    }
+}
+
+#[test]
+fn check_down_model() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let nb_vars = 6;
+
+    for _ in 0..10 {
+        // Clauses of three distinct variables that the planted assignment satisfies
+        let planted = DVector::from_fn(nb_vars, |_, _| rng.gen_bool(0.5));
+        let clause_list = (0..8)
+            .map(|_| loop {
+                let clause: Vec<_> = index::sample(&mut rng, nb_vars, 3)
+                    .into_iter()
+                    .map(|i| SatVariable(rng.gen_bool(0.5), i))
+                    .collect();
+
+                if clause.iter().any(|&SatVariable(is_true, i)| planted[i] == is_true) {
+                    break clause;
+                }
+            })
+            .collect();
+        let problem = KSatProblem { nb_vars, clause_list };
+
+        check_planted_down_model::<Nusslein>(&problem, &planted);
+        check_planted_down_model::<Nusslein23>(&problem, &planted);
+        check_planted_down_model::<Chain<ThreeSat, Chancellor, KSatProblem>>(&problem, &planted);
+    }
+}
+
+#[test]
+fn check_nusslein_negative_clause() {
+    let nb_vars = 3;
+    let problem = KSatProblem {
+        nb_vars,
+        clause_list: vec![vec![SatVariable(false, 0), SatVariable(false, 1), SatVariable(false, 2)]],
+    };
+
+    let (qubo_problem, _) = Nusslein::reduce(&problem);
+    let nb_aux = qubo_problem.get_size() - nb_vars;
+    let ground_energy = qubo_problem.evaluate(&ExhaustiveSearch::new().solve(&qubo_problem));
+
+    // Only the all-true assignment violates the clause, and only it misses the ground energy
+    for x in 0..1usize << nb_vars {
+        let min_energy = (0..1usize << nb_aux)
+            .map(|aux| {
+                qubo_problem.evaluate(&QuboSolution::from_fn(qubo_problem.get_size(), |i| {
+                    if i < nb_vars { x >> i & 1 == 1 } else { aux >> (i - nb_vars) & 1 == 1 }
+                }))
+            })
+            .min()
+            .unwrap();
+
+        if x == (1 << nb_vars) - 1 {
+            assert!(min_energy > ground_energy);
+        } else {
+            assert_eq!(ground_energy, min_energy, "{:03b} satisfies the clause", x);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use nalgebra::DVector;
+
+use crate::core::{DownModel, Reduction};
+use crate::qubo::{QuboError, QuboProblem, QuboSolution};
+use crate::sat::{MaxSatProblem, MaxSatSolution, SatVariable};
+use crate::sat::reductions::NO_ASSIGNMENT;
+use crate::sat::reductions::qubo::descend_auxiliaries;
+
+type PenaltyType = i64;
+
+/// Reduces a weighted MaxSAT problem by adding `w * (1 - l_1)(1 - l_2)...(1 - l_k)` for every clause,
+/// which is exactly `w` when the clause is violated and 0 otherwise. Hard clauses are given a weight
+/// greater than the sum of all the soft clauses, so violating one always costs more than violating
+/// every soft clause.
+///
+/// Cubic terms are quadratised with one auxiliary variable using Ishikawa's reduction, and higher
+/// order terms are first reduced by substituting `z = y_1 y_2` with a Rosenberg penalty. Both are
+/// exact when minimised over the auxiliary variables.
+///
+/// [Reduction::reduce] panics when the weights overflow the penalty type; use
+/// [WeightedPenalty::try_reduce] to get an error instead.
+pub struct WeightedPenalty {
+    problem: MaxSatProblem,
+    offset: PenaltyType,
+}
+
+/// One factor of a penalty term: `Factor(i, true)` is `x_i` and `Factor(i, false)` is `1 - x_i`
+#[derive(Clone, Copy)]
+struct Factor(usize, bool);
+
+struct PenaltyBuilder {
+    problem_size: usize,
+    triplets: Vec<(usize, usize, PenaltyType)>,
+    offset: PenaltyType,
+    // The sum of the absolute values of every term pushed, which bounds every coefficient of `Q`
+    magnitude: PenaltyType,
+}
+
+fn scale(weight: PenaltyType, factor: PenaltyType) -> Result<PenaltyType, QuboError> {
+    weight.checked_mul(factor).ok_or(QuboError::Overflow)
+}
+
+impl PenaltyBuilder {
+    fn new_variable(&mut self) -> usize {
+        self.problem_size += 1;
+
+        self.problem_size - 1
+    }
+
+    fn push(&mut self, i: usize, j: usize, weight: PenaltyType) -> Result<(), QuboError> {
+        self.magnitude = self.magnitude.checked_add(weight.abs()).ok_or(QuboError::Overflow)?;
+        self.triplets.push((usize::min(i, j), usize::max(i, j), weight));
+
+        Ok(())
+    }
+
+    fn constant(&mut self, weight: PenaltyType) -> Result<(), QuboError> {
+        self.offset = self.offset.checked_add(weight).ok_or(QuboError::Overflow)?;
+
+        Ok(())
+    }
+
+    fn linear(&mut self, Factor(i, is_plain): Factor, weight: PenaltyType) -> Result<(), QuboError> {
+        if is_plain {
+            self.push(i, i, weight)
+        } else {
+            self.constant(weight)?;
+            self.push(i, i, -weight)
+        }
+    }
+
+    fn quadratic(
+        &mut self,
+        a @ Factor(i, is_plain_i): Factor,
+        b @ Factor(j, is_plain_j): Factor,
+        weight: PenaltyType,
+    ) -> Result<(), QuboError> {
+        if i == j {
+            // x * x = x and x * (1 - x) = 0
+            if is_plain_i == is_plain_j {
+                self.linear(a, weight)?;
+            }
+
+            return Ok(());
+        }
+
+        match (is_plain_i, is_plain_j) {
+            (true, true) => self.push(i, j, weight),
+            (false, true) => {
+                // (1 - x_i) x_j = x_j - x_i x_j
+                self.linear(b, weight)?;
+                self.push(i, j, -weight)
+            }
+            (true, false) => {
+                // x_i (1 - x_j) = x_i - x_i x_j
+                self.linear(a, weight)?;
+                self.push(i, j, -weight)
+            }
+            (false, false) => {
+                // (1 - x_i)(1 - x_j) = 1 - x_i - x_j + x_i x_j
+                self.constant(weight)?;
+                self.push(i, i, -weight)?;
+                self.push(j, j, -weight)?;
+                self.push(i, j, weight)
+            }
+        }
+    }
+
+    fn product(&mut self, factors: &[Factor], weight: PenaltyType) -> Result<(), QuboError> {
+        match *factors {
+            [] => self.constant(weight),
+            [y_1] => self.linear(y_1, weight),
+            [y_1, y_2] => self.quadratic(y_1, y_2, weight),
+            [y_1, y_2, y_3] => {
+                // y_1 y_2 y_3 = min_w (y_1 y_2 + y_1 y_3 + y_2 y_3 - w (y_1 + y_2 + y_3 - 1))
+                let w = Factor(self.new_variable(), true);
+
+                self.quadratic(y_1, y_2, weight)?;
+                self.quadratic(y_1, y_3, weight)?;
+                self.quadratic(y_2, y_3, weight)?;
+                self.quadratic(w, y_1, -weight)?;
+                self.quadratic(w, y_2, -weight)?;
+                self.quadratic(w, y_3, -weight)?;
+                self.linear(w, weight)
+            }
+            [y_1, y_2, ref rest @ ..] => {
+                // z = y_1 y_2 is enforced by y_1 y_2 - 2 y_1 z - 2 y_2 z + 3z, which is at least 1 otherwise
+                let z = Factor(self.new_variable(), true);
+
+                self.quadratic(y_1, y_2, weight)?;
+                self.quadratic(y_1, z, scale(weight, -2)?)?;
+                self.quadratic(y_2, z, scale(weight, -2)?)?;
+                self.linear(z, scale(weight, 3)?)?;
+
+                let reduced: Vec<_> = [z].into_iter().chain(rest.iter().cloned()).collect();
+                self.product(&reduced, weight)
+            }
+        }
+    }
+
+    fn clause(&mut self, clause: &[SatVariable], weight: PenaltyType) -> Result<(), QuboError> {
+        // A literal is false exactly when its variable is the opposite of its sign
+        let factors: Vec<_> = clause
+            .iter()
+            .map(|&SatVariable(is_true, var)| Factor(var, !is_true))
+            .collect();
+
+        self.product(&factors, weight)
+    }
+}
+
+impl WeightedPenalty {
+    /// The constant dropped from the QUBO. For any assignment `x`, the minimum over the auxiliary
+    /// variables of `E(x) / 2 + offset` is the weighted number of violated clauses.
+    pub fn offset(&self) -> PenaltyType {
+        self.offset
+    }
+
+    /// Reduces the problem, or returns [QuboError::Overflow] when a coefficient or an energy of the
+    /// QUBO may not fit in the penalty type.
+    pub fn try_reduce(max_sat_problem: &MaxSatProblem) -> Result<(QuboProblem<PenaltyType>, Self), QuboError> {
+        let to_penalty = |weight| PenaltyType::try_from(weight).or(Err(QuboError::Overflow));
+
+        let hard_weight = max_sat_problem
+            .soft_clauses
+            .iter()
+            .try_fold(1 as PenaltyType, |total, &(weight, _)| {
+                total.checked_add(to_penalty(weight)?).ok_or(QuboError::Overflow)
+            })?;
+
+        let mut builder = PenaltyBuilder {
+            problem_size: max_sat_problem.nb_vars,
+            triplets: Vec::new(),
+            offset: 0,
+            magnitude: 0,
+        };
+
+        for clause in &max_sat_problem.hard_clauses {
+            builder.clause(clause, hard_weight)?;
+        }
+
+        for (weight, clause) in &max_sat_problem.soft_clauses {
+            builder.clause(clause, to_penalty(*weight)?)?;
+        }
+
+        let PenaltyBuilder { problem_size, triplets, offset, magnitude } = builder;
+
+        // Q + Q^T is stored, so its coefficients and energies are bounded by twice the magnitude
+        scale(magnitude, 2)?;
+
+        let q_matrix = QuboProblem::try_from_triplets(problem_size, triplets)
+            .expect("Matrix should be properly constructed.");
+
+        Ok((
+            q_matrix,
+            Self {
+                problem: max_sat_problem.clone(),
+                offset,
+            },
+        ))
+    }
+}
+
+impl Reduction<MaxSatProblem, QuboProblem<PenaltyType>> for WeightedPenalty {
+    fn reduce(max_sat_problem: &MaxSatProblem) -> (QuboProblem<PenaltyType>, Self) {
+        Self::try_reduce(max_sat_problem).expect("Clause weights should fit in the penalty type")
+    }
+
+    fn up_model(&self, solution: QuboSolution) -> MaxSatSolution {
+        self.problem
+            .solution_from_assignment(DVector::from_fn(self.problem.nb_vars, |i, _| solution.get(i)))
+    }
+}
+
+impl DownModel<MaxSatProblem, QuboProblem<PenaltyType>> for WeightedPenalty {
+    fn down_model(&self, qubo_problem: &QuboProblem<PenaltyType>, solution: MaxSatSolution) -> QuboSolution {
+        let assignment = solution.assignment().expect(NO_ASSIGNMENT);
+        let solution = QuboSolution::from_fn(qubo_problem.get_size(), |i| i < self.problem.nb_vars && assignment[i]);
+
+        descend_auxiliaries(qubo_problem, solution, self.problem.nb_vars)
+    }
+}
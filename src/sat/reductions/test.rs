@@ -0,0 +1,64 @@
+use nalgebra::DVector;
+use rand::prelude::*;
+
+use crate::core::{BoxedReduction, Reduction};
+use crate::sat::reductions::three_sat::ThreeSat;
+use crate::sat::{KSatProblem, SatSolution, SatVariable};
+
+fn random_problem(rng: &mut impl Rng, nb_vars: usize, nb_clauses: usize, max_length: usize) -> KSatProblem {
+    KSatProblem {
+        nb_vars,
+        clause_list: (0..nb_clauses)
+            .map(|_| {
+                (0..rng.gen_range(1..=max_length))
+                    .map(|_| SatVariable(rng.gen_bool(0.5), rng.gen_range(0..nb_vars)))
+                    .collect()
+            })
+            .collect(),
+    }
+}
+
+fn brute_force(problem: &KSatProblem) -> SatSolution {
+    (0..1usize << problem.nb_vars)
+        .map(|x| SatSolution::Sat(DVector::from_fn(problem.nb_vars, |i, _| x >> i & 1 == 1)))
+        .find(|solution| problem.evaluate(solution))
+        .unwrap_or(SatSolution::Unsat)
+}
+
+#[test]
+fn check_three_sat() {
+    let mut rng = StdRng::seed_from_u64(3);
+
+    for _ in 0..20 {
+        let problem = random_problem(&mut rng, 4, 6, 5);
+        let (three_sat_problem, reducer) = ThreeSat::reduce(&problem);
+
+        assert!(three_sat_problem.clause_list.iter().all(|clause| clause.len() == 3));
+
+        match (reducer.up_model(brute_force(&three_sat_problem)), brute_force(&problem)) {
+            (solution @ SatSolution::Sat(_), SatSolution::Sat(_)) => assert!(problem.evaluate(&solution)),
+            (SatSolution::Unsat, SatSolution::Unsat) => {}
+            (solution, expected) => panic!("Got {} but expected {}", solution, expected),
+        }
+    }
+}
+
+#[test]
+fn check_boxed_reduction() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let problem = random_problem(&mut rng, 4, 4, 4);
+
+    let (three_sat_problem, up_modeller) = BoxedReduction::reduce::<ThreeSat>(&problem);
+    let (same_problem, identity) = BoxedReduction::reduce::<ThreeSat>(&three_sat_problem);
+    let up_modeller = up_modeller.then(identity);
+
+    // Exact 3-SAT problems are left untouched
+    assert_eq!(same_problem.nb_vars, three_sat_problem.nb_vars);
+    assert_eq!(format!("{:?}", same_problem.clause_list), format!("{:?}", three_sat_problem.clause_list));
+
+    let SatSolution::Sat(assignment) = up_modeller.up_model(brute_force(&same_problem)) else {
+        panic!("Expected a satisfiable problem");
+    };
+    assert_eq!(assignment.len(), problem.nb_vars);
+    assert!(problem.evaluate(&SatSolution::Sat(assignment)));
+}
@@ -0,0 +1,105 @@
+use nalgebra::DVector;
+
+use crate::core::{DownModel, Reduction};
+use crate::sat::{KSatProblem, SatSolution, SatVariable};
+use crate::sat::reductions::NO_ASSIGNMENT;
+
+/// Reduces k-SAT to exact 3-SAT, where every clause has exactly three literals.
+///
+/// Longer clauses are split into a chain of clauses linked by fresh variables,
+/// `(l_1 + l_2 + y_1) . (¬y_1 + l_3 + y_2) . ... . (¬y_(k-3) + l_(k-1) + l_k)`, and shorter clauses are
+/// padded with fresh variables in both polarities, e.g. `(l_1 + l_2 + z) . (l_1 + l_2 + ¬z)`. Both
+/// preserve satisfiability, and the original variables keep their indices.
+pub struct ThreeSat(usize);
+
+fn pad_clause(clause: &[SatVariable], nb_vars: &mut usize, clauses: &mut Vec<Vec<SatVariable>>) {
+    if clause.len() == 3 {
+        clauses.push(clause.to_vec());
+        return;
+    }
+
+    let z = *nb_vars;
+    *nb_vars += 1;
+
+    for is_true in [true, false] {
+        let padded: Vec<_> = clause.iter().cloned().chain([SatVariable(is_true, z)]).collect();
+
+        pad_clause(&padded, nb_vars, clauses);
+    }
+}
+
+impl Reduction<KSatProblem, KSatProblem> for ThreeSat {
+    fn reduce(
+        &KSatProblem {
+            nb_vars,
+            ref clause_list,
+        }: &KSatProblem,
+    ) -> (KSatProblem, Self) {
+        let mut problem_size = nb_vars;
+        let mut clauses = Vec::with_capacity(clause_list.len());
+
+        for clause in clause_list {
+            let [first, second, ref middle @ .., second_last, last] = clause[..] else {
+                pad_clause(clause, &mut problem_size, &mut clauses);
+                continue;
+            };
+
+            let mut link = SatVariable(true, problem_size);
+            problem_size += 1;
+            clauses.push(vec![first, second, link]);
+
+            for &literal in middle {
+                let next_link = SatVariable(true, problem_size);
+                problem_size += 1;
+
+                clauses.push(vec![SatVariable(false, link.1), literal, next_link]);
+                link = next_link;
+            }
+
+            clauses.push(vec![SatVariable(false, link.1), second_last, last]);
+        }
+
+        (
+            KSatProblem {
+                nb_vars: problem_size,
+                clause_list: clauses,
+            },
+            Self(nb_vars),
+        )
+    }
+
+    fn up_model(&self, solution: SatSolution) -> SatSolution {
+        match solution {
+            SatSolution::Sat(assignment) => SatSolution::Sat(DVector::from_fn(self.0, |i, _| assignment[i])),
+            other => other,
+        }
+    }
+}
+
+impl DownModel<KSatProblem, KSatProblem> for ThreeSat {
+    /// Sets each fresh variable at its first occurrence, so that it satisfies its clause unless the
+    /// rest of the clause already does. Every clause is then satisfied when the assignment satisfies
+    /// the original problem.
+    fn down_model(&self, reduced_problem: &KSatProblem, solution: SatSolution) -> SatSolution {
+        let assignment = solution.assignment().expect(NO_ASSIGNMENT);
+
+        let mut values = DVector::from_fn(reduced_problem.nb_vars, |i, _| i < self.0 && assignment[i]);
+        let mut is_set: Vec<bool> = (0..reduced_problem.nb_vars).map(|i| i < self.0).collect();
+
+        for clause in &reduced_problem.clause_list {
+            let mut is_satisfied = clause
+                .iter()
+                .any(|&SatVariable(is_true, var)| is_set[var] && values[var] == is_true);
+
+            for &SatVariable(is_true, var) in clause {
+                if !is_set[var] {
+                    values[var] = is_true && !is_satisfied;
+                    is_set[var] = true;
+                    is_satisfied = true;
+                }
+            }
+        }
+
+        SatSolution::Sat(values)
+    }
+}
@@ -0,0 +1,91 @@
+use nalgebra::DVector;
+
+use crate::sat::{KSatProblem, KSatProblemError, MaxSatProblem, Position, SatSolution};
+
+fn parse(file: &str) -> Result<KSatProblem, KSatProblemError> {
+    KSatProblem::from_benchmark_file(file.as_bytes())
+}
+
+#[test]
+fn check_cnf_parsing() {
+    let file = "c start\n\
+                p cnf 3 4\n\
+                1 2\n   3 0 -1 0\n\
+                c between clauses\n\
+                \n\
+                2 3 0 -3\n\
+                0\n\
+                %\n\
+                0\n";
+
+    let problem = parse(file).unwrap();
+
+    assert_eq!(problem.nb_vars, 3);
+    assert_eq!(problem.clause_list.len(), 4);
+    assert_eq!(problem.clause_list.iter().map(Vec::len).collect::<Vec<_>>(), vec![3, 1, 2, 1]);
+    assert!(problem.evaluate(&SatSolution::Sat(DVector::from_vec(vec![false, true, false]))));
+    assert!(!problem.evaluate(&SatSolution::Sat(DVector::from_vec(vec![true, true, false]))));
+}
+
+#[test]
+fn check_cnf_errors() {
+    let at = |line, column| Position { line, column };
+
+    assert!(matches!(parse(""), Err(KSatProblemError::MissingHeader)));
+    assert!(matches!(parse("c only\n1 2 0\n"), Err(KSatProblemError::MissingHeader)));
+    assert!(matches!(
+        parse("p cnf 3\n1 0\n"),
+        Err(KSatProblemError::InvalidHeader { position }) if position == at(1, 1)
+    ));
+    // The clause count only warns when it is wrong, however large it claims to be
+    assert!(matches!(
+        parse("p cnf 3 18446744073709551615\n1 0\n"),
+        Ok(KSatProblem { clause_list, .. }) if clause_list.len() == 1
+    ));
+    assert!(matches!(
+        parse("p cnf 3 1\n1 x 0\n"),
+        Err(KSatProblemError::InvalidLiteral { token, position }) if token == "x" && position == at(2, 3)
+    ));
+    assert!(matches!(
+        parse("p cnf 3 1\n\n  1 -4 0\n"),
+        Err(KSatProblemError::InvalidVariable { variable: 4, position }) if position == at(3, 5)
+    ));
+    assert!(matches!(
+        parse("p cnf 3 2\n1 0\n2 -3\n"),
+        Err(KSatProblemError::UnterminatedClause { position }) if position == at(3, 1)
+    ));
+    assert!(matches!(
+        parse("p cnf 3 2\n1 0 0\n"),
+        Err(KSatProblemError::EmptyClause { position }) if position == at(2, 5)
+    ));
+}
+
+#[test]
+fn check_wcnf_parsing() {
+    let old_format = "c old format\np wcnf 3 4 10\n10 1 -2 0\n3 2 3 0\n1 -1 0\n10 -3 0\n";
+    let new_format = "c new format\nh 1 -2 0\n3 2\n3 0 1 -1 0\nh -3 0\n";
+
+    for file in [old_format, new_format] {
+        let problem = MaxSatProblem::from_benchmark_file(file.as_bytes()).unwrap();
+
+        assert_eq!(problem.nb_vars, 3);
+        assert_eq!(problem.hard_clauses.len(), 2);
+        assert_eq!(problem.soft_clauses.len(), 2);
+        assert_eq!(problem.soft_clauses[0].0, 3);
+        assert_eq!(problem.cost(&DVector::from_vec(vec![true, true, false])), Some(1));
+        assert_eq!(problem.cost(&DVector::from_vec(vec![false, true, true])), None);
+    }
+
+    assert!(matches!(
+        MaxSatProblem::from_benchmark_file("p wcnf 3 1 10\nx 1 0\n".as_bytes()),
+        Err(KSatProblemError::InvalidWeight { .. })
+    ));
+    assert!(matches!(
+        MaxSatProblem::from_benchmark_file("p wcnf 3 1 10\n1 1 2\n".as_bytes()),
+        Err(KSatProblemError::UnterminatedClause { .. })
+    ));
+    assert!(matches!(
+        MaxSatProblem::from_benchmark_file("p wcnf 1 1 10\n1 1 2 0\n".as_bytes()),
+        Err(KSatProblemError::InvalidVariable { variable: 2, .. })
+    ));
+}
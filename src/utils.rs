@@ -1,5 +1,35 @@
+use num_traits::ToPrimitive;
+use rand::{Rng, SeedableRng, thread_rng};
+use rand::rngs::StdRng;
+
 // fast ciel(log2(x + 1))
 #[inline(always)]
 pub fn fast_ceil_log2(x: usize) -> u32 {
     usize::BITS - x.leading_zeros()
 }
+
+/// Converts a weight into an `f64` for use in acceptance probabilities and other floating point
+/// calculations
+#[inline(always)]
+pub fn to_f64<T: ToPrimitive>(x: T) -> f64 {
+    x.to_f64().expect("Weights should always be representable as a f64")
+}
+
+/// A random seed, for runs that were not given one
+pub fn random_seed() -> u64 {
+    thread_rng().r#gen()
+}
+
+/// An independent random number generator for each stream of a seed. Solvers give every replica or
+/// worker its own stream, so that a seeded run does not depend on how its threads are scheduled.
+///
+/// This is what the `with_seed` of every stochastic solver relies on: runs with the same seed and
+/// parameters, including the parallelism, give identical results. Runs stopped by time or by a
+/// cancellation token are the exception, as where they stop depends on the machine.
+pub fn seeded_rng(seed: u64, stream: u64) -> StdRng {
+    let mut bytes = [0; 32];
+    bytes[..8].copy_from_slice(&seed.to_le_bytes());
+    bytes[8..16].copy_from_slice(&stream.to_le_bytes());
+
+    StdRng::from_seed(bytes)
+}